@@ -1,5 +1,6 @@
-use box2d_lite_rs::dynamics::{BodyDef, JointDef, World, WorldConfig};
-use box2d_lite_rs::math::{Mat22, Vec2};
+use box2d_lite_rs::collision::{BroadPhaseKind, Shape};
+use box2d_lite_rs::dynamics::{Body, BodyDef, BodyHandle, JointDef, JointHandle, World, WorldConfig};
+use box2d_lite_rs::math::{Mat22, Transform, Vec2};
 use macroquad::prelude::*;
 use ::rand::Rng;
 use ::rand::thread_rng;
@@ -100,7 +101,74 @@ impl Camera2DView {
     }
 }
 
-fn draw_body(view: &Camera2DView, position: Vec2, rotation: f32, half_extents: Vec2, color: Color) {
+fn draw_body(view: &Camera2DView, body: &Body, color: Color) {
+    for (k, fixture) in body.fixtures.iter().enumerate() {
+        draw_fixture(view, &fixture.shape, &body.fixture_transform(k), color);
+    }
+}
+
+fn draw_fixture(view: &Camera2DView, shape: &Shape, xf: &Transform, color: Color) {
+    let position = xf.position;
+    let rotation = xf.rotation.col1.y.atan2(xf.rotation.col1.x);
+    match *shape {
+        Shape::Box { width } => draw_box(view, position, rotation, 0.5 * width, color),
+        Shape::Circle { radius } => draw_circle_shape(view, position, rotation, radius, color),
+        Shape::Polygon(ref poly) => draw_polygon(view, position, rotation, poly.vertices(), color),
+        Shape::Capsule { center1, center2, radius } => {
+            let p1 = xf.apply(center1);
+            let p2 = xf.apply(center2);
+            let side = radius * Vec2::cross_vec_scalar(p2 - p1, 1.0).normalize();
+            draw_circle_shape(view, p1, rotation, radius, color);
+            draw_circle_shape(view, p2, rotation, radius, color);
+            for offset in [side, -side] {
+                let a = view.world_to_screen(p1 + offset);
+                let b = view.world_to_screen(p2 + offset);
+                draw_line(a.x, a.y, b.x, b.y, 1.5, color);
+            }
+        }
+        Shape::Segment(ref seg) => draw_segment(view, xf, seg.vertex1, seg.vertex2, color),
+        Shape::Chain(ref chain) => {
+            for k in 0..chain.edge_count() {
+                let seg = chain.child_edge(k);
+                draw_segment(view, xf, seg.vertex1, seg.vertex2, color);
+            }
+        }
+    }
+}
+
+fn draw_segment(view: &Camera2DView, xf: &Transform, v1: Vec2, v2: Vec2, color: Color) {
+    let p1 = view.world_to_screen(xf.apply(v1));
+    let p2 = view.world_to_screen(xf.apply(v2));
+    draw_line(p1.x, p1.y, p2.x, p2.y, 1.5, color);
+}
+
+fn draw_circle_shape(view: &Camera2DView, position: Vec2, rotation: f32, radius: f32, color: Color) {
+    let segments = 24;
+    let mut prev = view.world_to_screen(position + Vec2::new(radius, 0.0));
+    for k in 1..=segments {
+        let angle = 2.0 * std::f32::consts::PI * k as f32 / segments as f32;
+        let next = view.world_to_screen(position + radius * Vec2::new(angle.cos(), angle.sin()));
+        draw_line(prev.x, prev.y, next.x, next.y, 1.5, color);
+        prev = next;
+    }
+
+    // Spoke so rotation is visible.
+    let c = view.world_to_screen(position);
+    let rim = view.world_to_screen(position + Mat22::from_angle(rotation) * Vec2::new(radius, 0.0));
+    draw_line(c.x, c.y, rim.x, rim.y, 1.5, color);
+}
+
+fn draw_polygon(view: &Camera2DView, position: Vec2, rotation: f32, vertices: &[Vec2], color: Color) {
+    let r = Mat22::from_angle(rotation);
+    for (k, v) in vertices.iter().enumerate() {
+        let next = vertices[(k + 1) % vertices.len()];
+        let p1 = view.world_to_screen(position + r * *v);
+        let p2 = view.world_to_screen(position + r * next);
+        draw_line(p1.x, p1.y, p2.x, p2.y, 1.5, color);
+    }
+}
+
+fn draw_box(view: &Camera2DView, position: Vec2, rotation: f32, half_extents: Vec2, color: Color) {
     let r = Mat22::from_angle(rotation);
 
     let v1 = position + r * Vec2::new(-half_extents.x, -half_extents.y);
@@ -125,14 +193,12 @@ fn draw_joint_support(view: &Camera2DView, x: Vec2, p: Vec2) {
     draw_line(a.x, a.y, b.x, b.y, 1.0, Color::new(0.5, 0.5, 0.8, 1.0));
 }
 
-fn add_body(world: &mut World, def: BodyDef) -> usize {
-    let h = world.create_body(def);
-    h.0
+fn add_body(world: &mut World, def: BodyDef) -> BodyHandle {
+    world.create_body(def)
 }
 
-fn add_joint(world: &mut World, def: JointDef) -> usize {
-    let h = world.create_joint(def);
-    h.0
+fn add_joint(world: &mut World, def: JointDef) -> JointHandle {
+    world.create_joint(def)
 }
 
 fn init_demo(world: &mut World, demo: Demo) {
@@ -186,8 +252,8 @@ fn init_demo(world: &mut World, demo: Demo) {
             );
 
             add_joint(world, JointDef::new(
-                box2d_lite_rs::dynamics::BodyHandle(b1),
-                box2d_lite_rs::dynamics::BodyHandle(b2),
+                b1,
+                b2,
                 Vec2::new(0.0, 11.0),
             ));
         }
@@ -377,15 +443,15 @@ fn init_demo(world: &mut World, demo: Demo) {
             add_joint(
                 world,
                 JointDef::new(
-                    box2d_lite_rs::dynamics::BodyHandle(b1),
-                    box2d_lite_rs::dynamics::BodyHandle(b2),
+                    b1,
+                    b2,
                     Vec2::new(0.0, 1.0),
                 ),
             );
         }
         Demo::Demo7 => {
             // Ground
-            add_body(
+            let ground = add_body(
                 world,
                 BodyDef {
                     width: Vec2::new(100.0, 20.0),
@@ -398,7 +464,7 @@ fn init_demo(world: &mut World, demo: Demo) {
 
             let num_planks = 15;
             let mass = 50.0;
-            let mut planks: Vec<usize> = Vec::with_capacity(num_planks);
+            let mut planks: Vec<BodyHandle> = Vec::with_capacity(num_planks);
             for i in 0..num_planks {
                 let idx = add_body(
                     world,
@@ -417,11 +483,11 @@ fn init_demo(world: &mut World, demo: Demo) {
             // In C++ `bodies[0]` is the ground, and `bodies[1..=numPlanks]` are planks.
             // Joints are created for i=0..numPlanks (ground->p0, p0->p1, ... p13->p14)
             // plus one extra joint connecting last plank back to ground.
-            let ground_handle = box2d_lite_rs::dynamics::BodyHandle(0);
-            let mut chain: Vec<box2d_lite_rs::dynamics::BodyHandle> = Vec::with_capacity(num_planks + 1);
+            let ground_handle = ground;
+            let mut chain: Vec<BodyHandle> = Vec::with_capacity(num_planks + 1);
             chain.push(ground_handle);
             for idx in &planks {
-                chain.push(box2d_lite_rs::dynamics::BodyHandle(*idx));
+                chain.push(*idx);
             }
 
             let frequency_hz = 2.0;
@@ -521,8 +587,8 @@ fn init_demo(world: &mut World, demo: Demo) {
             add_joint(
                 world,
                 JointDef::new(
-                    box2d_lite_rs::dynamics::BodyHandle(b1),
-                    box2d_lite_rs::dynamics::BodyHandle(b3),
+                    b1,
+                    b3,
                     Vec2::new(-2.0, 1.0),
                 ),
             );
@@ -540,8 +606,8 @@ fn init_demo(world: &mut World, demo: Demo) {
             add_joint(
                 world,
                 JointDef::new(
-                    box2d_lite_rs::dynamics::BodyHandle(b2),
-                    box2d_lite_rs::dynamics::BodyHandle(b4),
+                    b2,
+                    b4,
                     Vec2::new(-7.0, 15.0),
                 ),
             );
@@ -559,8 +625,8 @@ fn init_demo(world: &mut World, demo: Demo) {
             add_joint(
                 world,
                 JointDef::new(
-                    box2d_lite_rs::dynamics::BodyHandle(b1),
-                    box2d_lite_rs::dynamics::BodyHandle(b5),
+                    b1,
+                    b5,
                     Vec2::new(6.0, 2.6),
                 ),
             );
@@ -576,8 +642,8 @@ fn init_demo(world: &mut World, demo: Demo) {
             add_joint(
                 world,
                 JointDef::new(
-                    box2d_lite_rs::dynamics::BodyHandle(b5),
-                    box2d_lite_rs::dynamics::BodyHandle(b6),
+                    b5,
+                    b6,
                     Vec2::new(7.0, 3.5),
                 ),
             );
@@ -621,8 +687,8 @@ fn init_demo(world: &mut World, demo: Demo) {
                 );
 
                 let mut jd = JointDef::new(
-                    box2d_lite_rs::dynamics::BodyHandle(prev),
-                    box2d_lite_rs::dynamics::BodyHandle(bi),
+                    prev,
+                    bi,
                     Vec2::new(i as f32, y),
                 );
                 jd.softness = softness;
@@ -635,7 +701,7 @@ fn init_demo(world: &mut World, demo: Demo) {
     }
 }
 
-fn launch_bomb(world: &mut World, bomb_index: &mut Option<usize>) {
+fn launch_bomb(world: &mut World, bomb_index: &mut Option<BodyHandle>) {
     let idx = match bomb_index {
         Some(i) => *i,
         None => {
@@ -646,6 +712,7 @@ fn launch_bomb(world: &mut World, bomb_index: &mut Option<usize>) {
                     position: Vec2::new(1.0, 1.0),
                     friction: 0.2,
                     mass: Some(50.0),
+                    bullet: true,
                     ..Default::default()
                 },
             );
@@ -655,11 +722,12 @@ fn launch_bomb(world: &mut World, bomb_index: &mut Option<usize>) {
     };
 
     let mut rng = thread_rng();
-    let b = &mut world.bodies[idx];
+    let b = world.body_mut(idx);
     b.position = Vec2::new(rng.gen_range(-15.0..=15.0), 15.0);
     b.rotation = rng.gen_range(-1.5..=1.5);
     b.velocity = -1.5 * b.position;
     b.angular_velocity = rng.gen_range(-20.0..=20.0);
+    b.set_awake(true);
 }
 
 #[macroquad::main("box2d-lite-rs samples")]
@@ -671,11 +739,12 @@ async fn main() {
             accumulate_impulses: true,
             warm_starting: true,
             position_correction: true,
+            ..Default::default()
         },
     );
 
     let mut demo = Demo::Demo1;
-    let mut bomb_index: Option<usize> = None;
+    let mut bomb_index: Option<BodyHandle> = None;
     let mut view = Camera2DView::default();
 
     let mut paused = false;
@@ -722,6 +791,20 @@ async fn main() {
         if is_key_pressed(KeyCode::P) {
             world.config.position_correction = !world.config.position_correction;
         }
+        if is_key_pressed(KeyCode::B) {
+            // The broad-phase is picked when the world is created.
+            let config = WorldConfig {
+                broad_phase: match world.config.broad_phase {
+                    BroadPhaseKind::DynamicTree => BroadPhaseKind::SweepAndPrune,
+                    BroadPhaseKind::SweepAndPrune => BroadPhaseKind::BruteForce,
+                    BroadPhaseKind::BruteForce => BroadPhaseKind::DynamicTree,
+                },
+                ..world.config
+            };
+            world = World::with_config(world.gravity, world.iterations, config);
+            bomb_index = None;
+            init_demo(&mut world, demo);
+        }
 
         if is_key_pressed(KeyCode::K) {
             paused = !paused;
@@ -764,27 +847,26 @@ async fn main() {
             last_mouse = None;
         }
 
-        if !paused {
-            world.step(dt);
-        } else if is_key_pressed(KeyCode::N) {
+        if !paused || is_key_pressed(KeyCode::N) {
             world.step(dt);
         }
 
         clear_background(BLACK);
 
         // Draw bodies
-        for (i, b) in world.bodies.iter().enumerate() {
-            let half = 0.5 * b.width;
-            let color = if Some(i) == bomb_index {
+        for (h, b) in world.bodies.iter() {
+            let color = if Some(h) == bomb_index {
                 Color::new(0.4, 0.9, 0.4, 1.0)
+            } else if !b.is_awake() && b.inv_mass > 0.0 {
+                Color::new(0.5, 0.5, 0.6, 1.0)
             } else {
                 Color::new(0.8, 0.8, 0.9, 1.0)
             };
-            draw_body(&view, b.position, b.rotation, half, color);
+            draw_body(&view, b, color);
         }
 
         // Draw joints
-        for j in &world.joints {
+        for j in world.joints.values() {
             // Match the C++ visualization: draw body center -> anchor for each body.
             let (x1, p1, x2, p2) = j.body_centers_and_anchors(&world);
             draw_joint_support(&view, x1, p1);
@@ -801,7 +883,7 @@ async fn main() {
 
         // UI overlay
         let overlay = format!(
-            "{}\nKeys: 1-9 demos | Space bomb | A accum | P posCorr | W warm | K pause | N step | R reset view\nArrows pan | Wheel zoom | RMB drag pan\nzoom={:.2} pan=({:.2},{:.2})\naccum={} posCorr={} warm={} bodies={} joints={}",
+            "{}\nKeys: 1-9 demos | Space bomb | A accum | P posCorr | W warm | B broad-phase | K pause | N step | R reset view\nArrows pan | Wheel zoom | RMB drag pan\nzoom={:.2} pan=({:.2},{:.2})\naccum={} posCorr={} warm={} broad-phase={:?} bodies={} joints={}",
             demo.name(),
             view.zoom,
             view.pan.x,
@@ -809,6 +891,7 @@ async fn main() {
             world.config.accumulate_impulses,
             world.config.position_correction,
             world.config.warm_starting,
+            world.config.broad_phase,
             world.bodies.len(),
             world.joints.len()
         );
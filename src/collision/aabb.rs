@@ -0,0 +1,97 @@
+use crate::math::Vec2;
+
+/// Axis-aligned bounding box.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Aabb {
+    pub lower: Vec2,
+    pub upper: Vec2,
+}
+
+impl Aabb {
+    #[inline]
+    pub const fn new(lower: Vec2, upper: Vec2) -> Self {
+        Self { lower, upper }
+    }
+
+    /// Smallest box containing all `points`.
+    pub fn from_points(points: &[Vec2]) -> Self {
+        let mut aabb = Self::new(points[0], points[0]);
+        for v in &points[1..] {
+            aabb.lower = Vec2::new(aabb.lower.x.min(v.x), aabb.lower.y.min(v.y));
+            aabb.upper = Vec2::new(aabb.upper.x.max(v.x), aabb.upper.y.max(v.y));
+        }
+        aabb
+    }
+
+    #[inline]
+    pub fn size(&self) -> Vec2 {
+        self.upper - self.lower
+    }
+
+    #[inline]
+    pub fn center(&self) -> Vec2 {
+        0.5 * (self.lower + self.upper)
+    }
+
+    #[inline]
+    pub fn perimeter(&self) -> f32 {
+        let size = self.size();
+        2.0 * (size.x + size.y)
+    }
+
+    #[inline]
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb::new(
+            Vec2::new(
+                self.lower.x.min(other.lower.x),
+                self.lower.y.min(other.lower.y),
+            ),
+            Vec2::new(
+                self.upper.x.max(other.upper.x),
+                self.upper.y.max(other.upper.y),
+            ),
+        )
+    }
+
+    /// Grow by `margin` on every side.
+    #[inline]
+    pub fn extended(&self, margin: f32) -> Aabb {
+        let r = Vec2::new(margin, margin);
+        Aabb::new(self.lower - r, self.upper + r)
+    }
+
+    #[inline]
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        self.lower.x <= other.upper.x
+            && self.lower.y <= other.upper.y
+            && other.lower.x <= self.upper.x
+            && other.lower.y <= self.upper.y
+    }
+
+    #[inline]
+    pub fn contains(&self, other: &Aabb) -> bool {
+        self.lower.x <= other.lower.x
+            && self.lower.y <= other.lower.y
+            && other.upper.x <= self.upper.x
+            && other.upper.y <= self.upper.y
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn union_overlap_and_containment() {
+        let a = Aabb::new(Vec2::new(0.0, 0.0), Vec2::new(1.0, 1.0));
+        let b = Aabb::new(Vec2::new(2.0, -1.0), Vec2::new(3.0, 0.5));
+        assert!(!a.overlaps(&b));
+
+        let u = a.union(&b);
+        assert!(u.contains(&a) && u.contains(&b));
+        assert_relative_eq!(u.size().x, 3.0);
+        assert_relative_eq!(u.size().y, 2.0);
+        assert!(a.extended(0.6).overlaps(&b.extended(0.6)));
+    }
+}
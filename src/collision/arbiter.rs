@@ -1,5 +1,5 @@
-use crate::collision::{EdgeNumber, collide};
-use crate::dynamics::{Body, BodyHandle, World, WorldConfig, bodies_two_mut};
+use crate::collision::{EdgeNumber, collide_fixtures};
+use crate::dynamics::{Body, BodyHandle, SlotMap, World, WorldConfig, bodies_two_mut};
 use crate::math::Vec2;
 
 #[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
@@ -24,10 +24,10 @@ impl FeaturePair {
     /// Pack into a stable 32-bit key (little-endian layout).
     #[inline]
     pub const fn key(self) -> u32 {
-        (self.in_edge1 as u32)
-            | ((self.out_edge1 as u32) << 8)
-            | ((self.in_edge2 as u32) << 16)
-            | ((self.out_edge2 as u32) << 24)
+        (self.in_edge1.0 as u32)
+            | ((self.out_edge1.0 as u32) << 8)
+            | ((self.in_edge2.0 as u32) << 16)
+            | ((self.out_edge2.0 as u32) << 24)
     }
 
     /// Inverse of `key()`.
@@ -55,20 +55,50 @@ pub struct Contact {
     pub mass_normal: f32,
     pub mass_tangent: f32,
     pub bias: f32,
+    /// Normal relative velocity before the step's impulses.
+    pub relative_velocity: f32,
     pub feature: FeaturePair,
 }
 
+/// Identifies a contact pair: two bodies, a fixture of each, and a child
+/// shape of each fixture (the edge index for chains, zero otherwise).
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
 pub struct ArbiterKey {
     pub body1: BodyHandle,
     pub body2: BodyHandle,
+    pub fixture1: usize,
+    pub fixture2: usize,
+    pub child1: usize,
+    pub child2: usize,
 }
 
 impl ArbiterKey {
     #[inline]
     pub fn new(b1: BodyHandle, b2: BodyHandle) -> Self {
-        let (body1, body2) = if b1 <= b2 { (b1, b2) } else { (b2, b1) };
-        Self { body1, body2 }
+        Self::with_children(b1, (0, 0), b2, (0, 0))
+    }
+
+    /// Key for a `(fixture, child)` of `b1` against one of `b2`.
+    #[inline]
+    pub fn with_children(
+        b1: BodyHandle,
+        c1: (usize, usize),
+        b2: BodyHandle,
+        c2: (usize, usize),
+    ) -> Self {
+        let (body1, c1, body2, c2) = if b1 <= b2 {
+            (b1, c1, b2, c2)
+        } else {
+            (b2, c2, b1, c1)
+        };
+        Self {
+            body1,
+            body2,
+            fixture1: c1.0,
+            fixture2: c2.0,
+            child1: c1.1,
+            child2: c2.1,
+        }
     }
 }
 
@@ -81,23 +111,67 @@ pub struct Arbiter {
     pub body1: BodyHandle,
     pub body2: BodyHandle,
 
+    /// Mixed from the fixtures' materials; see [`Material::combine`].
+    ///
+    /// [`Material::combine`]: crate::dynamics::Material::combine
     pub friction: f32,
+    pub restitution: f32,
+    /// Rolling resistance times the larger rounding radius of the two
+    /// shapes: the lever arm of the normal impulse against rolling.
+    pub rolling_resistance: f32,
+    pub tangent_speed: f32,
+    /// Accumulated angular impulse against rolling.
+    pub rolling_impulse: f32,
+    /// Whether the solver applies this contact. Reset every step, before
+    /// [`ContactListener::pre_solve`](crate::dynamics::ContactListener::pre_solve).
+    pub enabled: bool,
 }
 
 impl Arbiter {
     #[inline]
     pub fn new(b1: BodyHandle, b2: BodyHandle, world: &World) -> Self {
-        let (body1, body2) = if b1 <= b2 { (b1, b2) } else { (b2, b1) };
+        Self::from_key(ArbiterKey::new(b1, b2), world)
+    }
+
+    /// Collide the fixture children named by `key`.
+    #[inline]
+    pub fn from_key(key: ArbiterKey, world: &World) -> Self {
+        let b1 = world.body(key.body1);
+        let b2 = world.body(key.body2);
+        let f1 = &b1.fixtures[key.fixture1];
+        let f2 = &b2.fixtures[key.fixture2];
+
         let mut contacts = [Contact::default(); MAX_POINTS];
-        let num_contacts = collide(&mut contacts, body1, body2, world);
+        let num_contacts = collide_fixtures(
+            &mut contacts,
+            b1,
+            (key.fixture1, key.child1),
+            b2,
+            (key.fixture2, key.child2),
+            world.config.face_tolerance,
+        );
 
-        Self {
-            contacts: contacts,
-            num_contacts: num_contacts,
-            body1: body1,
-            body2: body2,
+        let material = f1
+            .material
+            .combine(&f2.material, &world.config.combine_rules);
+        let radius = f1
+            .shape
+            .child(key.child1)
+            .radius()
+            .max(f2.shape.child(key.child2).radius());
 
-            friction: (world.body(body1).friction * world.body(body2).friction).sqrt(),
+        Self {
+            contacts,
+            num_contacts,
+            body1: key.body1,
+            body2: key.body2,
+
+            friction: material.friction,
+            restitution: material.restitution,
+            rolling_resistance: material.rolling_resistance * radius,
+            tangent_speed: material.tangent_speed,
+            rolling_impulse: 0.0,
+            enabled: true,
         }
     }
 
@@ -129,18 +203,37 @@ impl Arbiter {
             }
             merged_contacts[i] = c;
         }
-        for i in 0..new_contacts.len() {
-            self.contacts[i] = merged_contacts[i];
-        }
+        self.contacts[..new_contacts.len()].copy_from_slice(&merged_contacts[..new_contacts.len()]);
         self.num_contacts = new_contacts.len();
+        if !warm_starting {
+            self.rolling_impulse = 0.0;
+        }
     }
 
-    pub fn pre_step(&mut self, inv_dt: f32, bodies: &mut [Body], config: &WorldConfig) {
-        let k_allowed_penetration: f32 = 0.01;
-        let k_bias_factor: f32 = if config.position_correction { 0.2 } else { 0.0 };
+    pub fn pre_step(
+        &mut self,
+        inv_dt: f32,
+        bodies: &mut SlotMap<BodyHandle, Body>,
+        config: &WorldConfig,
+    ) {
+        let bias_factor = if config.position_correction {
+            config.bias_factor
+        } else {
+            0.0
+        };
 
         let (body1, body2) = bodies_two_mut(bodies, self.body1, self.body2);
 
+        // Approach speeds for restitution, taken before warm starting.
+        for c in &mut self.contacts[..self.num_contacts] {
+            let r1 = c.position - body1.position;
+            let r2 = c.position - body2.position;
+            let dv = body2.velocity + Vec2::cross_scalar_vec(body2.angular_velocity, r2)
+                - body1.velocity
+                - Vec2::cross_scalar_vec(body1.angular_velocity, r1);
+            c.relative_velocity = dv.dot(c.normal);
+        }
+
         for c in &mut self.contacts[..self.num_contacts] {
             let r1 = c.position - body1.position;
             let r2 = c.position - body2.position;
@@ -161,7 +254,13 @@ impl Arbiter {
                 body1.inv_i * (r1.dot(r1) - rt1 * rt1) + body2.inv_i * (r2.dot(r2) - rt2 * rt2);
             c.mass_tangent = 1.0 / k_tangent;
 
-            c.bias = -k_bias_factor * inv_dt * (c.separation + k_allowed_penetration).min(0.0);
+            c.bias = -bias_factor * inv_dt * (c.separation + config.allowed_penetration).min(0.0);
+            c.bias = c.bias.min(config.max_linear_correction * inv_dt);
+            if c.relative_velocity < -config.restitution_threshold {
+                // Separate at the scaled approach speed, unless the position
+                // bias asks for more.
+                c.bias = c.bias.max(-self.restitution * c.relative_velocity);
+            }
 
             if config.accumulate_impulses {
                 // Apply normal + friction impulse
@@ -174,9 +273,14 @@ impl Arbiter {
                 body2.angular_velocity += body2.inv_i * r2.cross(p);
             }
         }
+
+        if config.accumulate_impulses {
+            body1.angular_velocity -= body1.inv_i * self.rolling_impulse;
+            body2.angular_velocity += body2.inv_i * self.rolling_impulse;
+        }
     }
 
-    pub fn apply_impulse(&mut self, bodies: &mut [Body], config: &WorldConfig) {
+    pub fn apply_impulse(&mut self, bodies: &mut SlotMap<BodyHandle, Body>, config: &WorldConfig) {
         let (b1, b2) = if self.body1 <= self.body2 {
             (self.body1, self.body2)
         } else {
@@ -184,6 +288,8 @@ impl Arbiter {
         };
         let (b1, b2) = bodies_two_mut(bodies, b1, b2);
 
+        // Normal impulse of this iteration, or the accumulated one.
+        let mut normal_impulse = 0.0;
         for c in &mut self.contacts[..self.num_contacts] {
             c.r1 = c.position - b1.position;
             c.r2 = c.position - b2.position;
@@ -206,6 +312,11 @@ impl Arbiter {
             } else {
                 dpn = dpn.max(0.0);
             }
+            normal_impulse += if config.accumulate_impulses {
+                c.pn
+            } else {
+                dpn
+            };
 
             // Apply contact impulse
             let pn = dpn * c.normal;
@@ -222,7 +333,8 @@ impl Arbiter {
                 - Vec2::cross_scalar_vec(b1.angular_velocity, c.r1);
 
             let tangent = Vec2::cross_vec_scalar(c.normal, 1.0);
-            let vt = dv.dot(tangent);
+            // Slide relative to the moving surfaces.
+            let vt = dv.dot(tangent) - self.tangent_speed;
             let mut dpt = c.mass_tangent * (-vt);
 
             if config.accumulate_impulses {
@@ -247,12 +359,30 @@ impl Arbiter {
             b2.velocity += b2.inv_mass * pt;
             b2.angular_velocity += b2.inv_i * c.r2.cross(pt);
         }
+
+        let k_rolling = b1.inv_i + b2.inv_i;
+        if self.rolling_resistance > 0.0 && k_rolling > 0.0 {
+            // Angular impulse against relative rotation, bounded like friction.
+            let max_lambda = self.rolling_resistance * normal_impulse;
+            let mut d_lambda = -(b2.angular_velocity - b1.angular_velocity) / k_rolling;
+            if config.accumulate_impulses {
+                let old_impulse = self.rolling_impulse;
+                self.rolling_impulse = (old_impulse + d_lambda).clamp(-max_lambda, max_lambda);
+                d_lambda = self.rolling_impulse - old_impulse;
+            } else {
+                d_lambda = d_lambda.clamp(-max_lambda, max_lambda);
+            }
+
+            b1.angular_velocity -= b1.inv_i * d_lambda;
+            b2.angular_velocity += b2.inv_i * d_lambda;
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dynamics::SlotKey;
     use approx::assert_relative_eq;
 
     #[test]
@@ -270,11 +400,15 @@ mod tests {
 
     #[test]
     fn arbiter_key_orders_handles() {
-        let a = BodyHandle(5);
-        let b = BodyHandle(2);
+        let a = BodyHandle::from_parts(5, 0);
+        let b = BodyHandle::from_parts(2, 0);
         let key = ArbiterKey::new(a, b);
         assert_eq!(key.body1, b);
         assert_eq!(key.body2, a);
+
+        let key = ArbiterKey::with_children(a, (1, 7), b, (0, 0));
+        assert_eq!((key.body1, key.fixture1, key.child1), (b, 0, 0));
+        assert_eq!((key.body2, key.fixture2, key.child2), (a, 1, 7));
     }
 
     #[test]
@@ -282,9 +416,14 @@ mod tests {
         let mut arb = Arbiter {
             contacts: [Contact::default(); MAX_POINTS],
             num_contacts: 1,
-            body1: BodyHandle(0),
-            body2: BodyHandle(1),
+            body1: BodyHandle::from_parts(0, 0),
+            body2: BodyHandle::from_parts(1, 0),
             friction: 0.0,
+            restitution: 0.0,
+            rolling_resistance: 0.0,
+            tangent_speed: 0.0,
+            rolling_impulse: 0.0,
+            enabled: true,
         };
         arb.contacts[0].feature = FeaturePair::new(
             EdgeNumber::Edge1,
@@ -296,11 +435,13 @@ mod tests {
         arb.contacts[0].pt = -0.5;
         arb.contacts[0].pnb = 0.75;
 
-        let mut new_c = Contact::default();
-        new_c.feature = arb.contacts[0].feature;
-        new_c.pn = 0.0;
-        new_c.pt = 0.0;
-        new_c.pnb = 0.0;
+        let new_c = Contact {
+            feature: arb.contacts[0].feature,
+            pn: 0.0,
+            pt: 0.0,
+            pnb: 0.0,
+            ..Default::default()
+        };
 
         arb.update(&[new_c], true);
         assert_relative_eq!(arb.contacts[0].pn, 1.25);
@@ -0,0 +1,353 @@
+use crate::collision::{Aabb, DynamicTree, ProxyId, RayCastInput, SweepAndPrune};
+use crate::dynamics::BodyHandle;
+
+/// What a broad-phase proxy stands for: one collision child of a fixture.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
+pub struct FixtureProxy {
+    pub body: BodyHandle,
+    pub fixture: usize,
+    pub child: usize,
+}
+
+/// Finds candidate pairs for the narrow phase.
+///
+/// The world creates a proxy per fixture child, refits every proxy before
+/// each step and then asks for new pairs. A pair is kept until
+/// [`test_overlap`](BroadPhase::test_overlap) fails, so `update_pairs` only
+/// needs to report overlaps that began since the previous call; reporting a
+/// pair again is harmless.
+pub trait BroadPhase {
+    fn create_proxy(&mut self, aabb: Aabb, proxy: FixtureProxy) -> ProxyId;
+
+    fn destroy_proxy(&mut self, id: ProxyId);
+
+    /// Refit a proxy to its current AABB.
+    fn move_proxy(&mut self, id: ProxyId, aabb: Aabb);
+
+    /// The (possibly enlarged) AABB stored for a proxy.
+    fn fat_aabb(&self, id: ProxyId) -> Aabb;
+
+    fn proxy_count(&self) -> usize;
+
+    /// Report pairs of proxies that started overlapping.
+    fn update_pairs(&mut self, callback: &mut dyn FnMut(FixtureProxy, FixtureProxy));
+
+    /// Remove all proxies.
+    fn clear(&mut self);
+
+    /// Call `callback` with every proxy whose fat AABB overlaps `aabb`.
+    /// Return `false` from the callback to stop the query.
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(FixtureProxy) -> bool);
+
+    /// Call `callback` with the proxies the ray may hit. The callback returns
+    /// the new clip fraction: 0 stops the cast, a positive value clips the
+    /// ray and a negative one leaves it as it is.
+    ///
+    /// The default queries the AABB of the whole ray up front.
+    fn ray_cast(
+        &self,
+        input: &RayCastInput,
+        callback: &mut dyn FnMut(&RayCastInput, FixtureProxy) -> f32,
+    ) {
+        let mut candidates = Vec::new();
+        self.query(&input.aabb(), &mut |proxy| {
+            candidates.push(proxy);
+            true
+        });
+
+        let mut sub_input = *input;
+        for proxy in candidates {
+            let value = callback(&sub_input, proxy);
+            if value == 0.0 {
+                return;
+            }
+            if value > 0.0 {
+                sub_input.max_fraction = value;
+            }
+        }
+    }
+
+    #[inline]
+    fn test_overlap(&self, a: ProxyId, b: ProxyId) -> bool {
+        self.fat_aabb(a).overlaps(&self.fat_aabb(b))
+    }
+}
+
+/// Broad-phase algorithm, chosen in [`WorldConfig`](crate::dynamics::WorldConfig).
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum BroadPhaseKind {
+    #[default]
+    DynamicTree,
+    /// Better suited to scenes spread out along the x axis.
+    SweepAndPrune,
+    /// Tests every pair; the reference implementation.
+    BruteForce,
+}
+
+impl BroadPhaseKind {
+    /// An empty broad-phase fattening proxy AABBs by `margin`.
+    pub fn create(self, margin: f32) -> Box<dyn BroadPhase> {
+        match self {
+            BroadPhaseKind::DynamicTree => Box::new(TreeBroadPhase::new(margin)),
+            BroadPhaseKind::SweepAndPrune => Box::new(SweepAndPrune::new(margin)),
+            BroadPhaseKind::BruteForce => Box::new(BruteForceBroadPhase::new(margin)),
+        }
+    }
+}
+
+/// Broad-phase over a [`DynamicTree`]. Proxies that leave their fat AABB go
+/// into a move buffer; only those are queried for new pairs.
+#[derive(Clone, Debug)]
+pub struct TreeBroadPhase {
+    tree: DynamicTree<FixtureProxy>,
+    move_buffer: Vec<ProxyId>,
+}
+
+impl TreeBroadPhase {
+    pub fn new(margin: f32) -> Self {
+        Self {
+            tree: DynamicTree::new(margin),
+            move_buffer: Vec::new(),
+        }
+    }
+
+    #[inline]
+    pub fn tree(&self) -> &DynamicTree<FixtureProxy> {
+        &self.tree
+    }
+}
+
+impl BroadPhase for TreeBroadPhase {
+    fn create_proxy(&mut self, aabb: Aabb, proxy: FixtureProxy) -> ProxyId {
+        let id = self.tree.create_proxy(aabb, proxy);
+        self.move_buffer.push(id);
+        id
+    }
+
+    fn destroy_proxy(&mut self, id: ProxyId) {
+        self.move_buffer.retain(|&m| m != id);
+        self.tree.destroy_proxy(id);
+    }
+
+    fn move_proxy(&mut self, id: ProxyId, aabb: Aabb) {
+        if self.tree.move_proxy(id, aabb) {
+            self.move_buffer.push(id);
+        }
+    }
+
+    #[inline]
+    fn fat_aabb(&self, id: ProxyId) -> Aabb {
+        self.tree.fat_aabb(id)
+    }
+
+    #[inline]
+    fn proxy_count(&self) -> usize {
+        self.tree.proxy_count()
+    }
+
+    /// Report each new overlap involving a moved proxy once, then empty the
+    /// move buffer.
+    fn update_pairs(&mut self, callback: &mut dyn FnMut(FixtureProxy, FixtureProxy)) {
+        let mut pairs = Vec::new();
+        for &query_id in &self.move_buffer {
+            let fat = self.tree.fat_aabb(query_id);
+            self.tree.query(&fat, |id| {
+                // When both proxies moved, only the higher id reports.
+                let both_moved = self.tree.was_moved(id);
+                if id != query_id && !(both_moved && id > query_id) {
+                    pairs.push((query_id, id));
+                }
+                true
+            });
+        }
+
+        for id in self.move_buffer.drain(..) {
+            self.tree.clear_moved(id);
+        }
+
+        for (a, b) in pairs {
+            callback(self.tree.user_data(a), self.tree.user_data(b));
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(self.tree.margin());
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(FixtureProxy) -> bool) {
+        self.tree
+            .query(aabb, |id| callback(self.tree.user_data(id)));
+    }
+
+    /// Walks the tree along the ray, skipping subtrees beyond the current
+    /// clip fraction.
+    fn ray_cast(
+        &self,
+        input: &RayCastInput,
+        callback: &mut dyn FnMut(&RayCastInput, FixtureProxy) -> f32,
+    ) {
+        self.tree.ray_cast(input, |sub_input, id| {
+            callback(sub_input, self.tree.user_data(id))
+        });
+    }
+}
+
+/// Tests every pair of proxies on each update. Quadratic, but simple enough
+/// to check the other broad-phases against.
+#[derive(Clone, Debug)]
+pub struct BruteForceBroadPhase {
+    proxies: Vec<Option<(Aabb, FixtureProxy)>>,
+    free_ids: Vec<ProxyId>,
+    margin: f32,
+}
+
+impl BruteForceBroadPhase {
+    pub fn new(margin: f32) -> Self {
+        Self {
+            proxies: Vec::new(),
+            free_ids: Vec::new(),
+            margin,
+        }
+    }
+}
+
+impl BroadPhase for BruteForceBroadPhase {
+    fn create_proxy(&mut self, aabb: Aabb, proxy: FixtureProxy) -> ProxyId {
+        let entry = Some((aabb.extended(self.margin), proxy));
+        match self.free_ids.pop() {
+            Some(id) => {
+                self.proxies[id] = entry;
+                id
+            }
+            None => {
+                self.proxies.push(entry);
+                self.proxies.len() - 1
+            }
+        }
+    }
+
+    fn destroy_proxy(&mut self, id: ProxyId) {
+        debug_assert!(self.proxies[id].is_some(), "not a proxy");
+        self.proxies[id] = None;
+        self.free_ids.push(id);
+    }
+
+    fn move_proxy(&mut self, id: ProxyId, aabb: Aabb) {
+        let (fat, _) = self.proxies[id].as_mut().expect("not a proxy");
+        *fat = aabb.extended(self.margin);
+    }
+
+    #[inline]
+    fn fat_aabb(&self, id: ProxyId) -> Aabb {
+        self.proxies[id].expect("not a proxy").0
+    }
+
+    fn proxy_count(&self) -> usize {
+        self.proxies.len() - self.free_ids.len()
+    }
+
+    /// Report every overlapping pair.
+    fn update_pairs(&mut self, callback: &mut dyn FnMut(FixtureProxy, FixtureProxy)) {
+        let live: Vec<(Aabb, FixtureProxy)> = self.proxies.iter().flatten().copied().collect();
+        for (i, (aabb_a, a)) in live.iter().enumerate() {
+            for (aabb_b, b) in &live[i + 1..] {
+                if aabb_a.overlaps(aabb_b) {
+                    callback(*a, *b);
+                }
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(self.margin);
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(FixtureProxy) -> bool) {
+        for (fat, proxy) in self.proxies.iter().flatten() {
+            if fat.overlaps(aabb) && !callback(*proxy) {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamics::SlotKey;
+    use crate::math::Vec2;
+
+    fn proxy(body: u32) -> FixtureProxy {
+        FixtureProxy {
+            body: BodyHandle::from_parts(body, 0),
+            fixture: 0,
+            child: 0,
+        }
+    }
+
+    fn unit_box(x: f32) -> Aabb {
+        Aabb::new(Vec2::new(x, 0.0), Vec2::new(x + 1.0, 1.0))
+    }
+
+    #[test]
+    fn update_pairs_reports_new_overlaps_once() {
+        let mut bp = TreeBroadPhase::new(0.1);
+        let a = bp.create_proxy(unit_box(0.0), proxy(0));
+        bp.create_proxy(unit_box(0.5), proxy(1));
+        bp.create_proxy(unit_box(5.0), proxy(2));
+
+        let mut pairs = Vec::new();
+        bp.update_pairs(&mut |p, q| {
+            pairs.push((
+                p.body.index().min(q.body.index()),
+                p.body.index().max(q.body.index()),
+            ))
+        });
+        assert_eq!(pairs, vec![(0, 1)]);
+
+        // Nothing moved, nothing new.
+        pairs.clear();
+        bp.move_proxy(a, unit_box(0.02));
+        bp.update_pairs(&mut |p, q| pairs.push((p.body.index(), q.body.index())));
+        assert!(pairs.is_empty());
+
+        // Moving next to body 2 reports only that pair.
+        bp.move_proxy(a, unit_box(4.5));
+        bp.update_pairs(&mut |p, q| pairs.push((p.body.index(), q.body.index())));
+        assert_eq!(pairs, vec![(0, 2)]);
+    }
+
+    #[test]
+    fn every_kind_finds_the_brute_force_pairs() {
+        let boxes: Vec<Aabb> = (0..40)
+            .map(|k| {
+                let (x, y) = ((k % 8) as f32 * 0.9, (k / 8) as f32 * 1.3);
+                Aabb::new(Vec2::new(x, y), Vec2::new(x + 1.0, y + 1.0))
+            })
+            .collect();
+
+        let pairs_of = |kind: BroadPhaseKind| {
+            let mut bp = kind.create(0.1);
+            for (k, aabb) in boxes.iter().enumerate() {
+                bp.create_proxy(*aabb, proxy(k as u32));
+            }
+            assert_eq!(bp.proxy_count(), boxes.len());
+
+            let mut pairs = Vec::new();
+            bp.update_pairs(&mut |p, q| {
+                pairs.push((
+                    p.body.index().min(q.body.index()),
+                    p.body.index().max(q.body.index()),
+                ))
+            });
+            pairs.sort();
+            pairs.dedup();
+            pairs
+        };
+
+        let reference = pairs_of(BroadPhaseKind::BruteForce);
+        assert!(!reference.is_empty());
+        assert_eq!(pairs_of(BroadPhaseKind::DynamicTree), reference);
+        assert_eq!(pairs_of(BroadPhaseKind::SweepAndPrune), reference);
+    }
+}
@@ -1,7 +1,13 @@
-use crate::collision::FeaturePair;
 use crate::collision::arbiter::Contact;
-use crate::dynamics::{BodyHandle, World};
-use crate::math::{Mat22, Vec2, sign_nonzero};
+use crate::collision::collide_capsule::{
+    collide_capsule_and_circle, collide_capsules, collide_polygon_and_capsule,
+};
+use crate::collision::collide_circle::{collide_circles, collide_polygon_and_circle};
+use crate::collision::collide_edge::{collide_segment_and_circle, collide_segment_and_polygon};
+use crate::collision::collide_polygon::collide_polygons;
+use crate::collision::{FeaturePair, MAX_POLYGON_VERTICES, Polygon, Shape};
+use crate::dynamics::{Body, BodyHandle, World};
+use crate::math::{Mat22, Transform, Vec2, sign_nonzero};
 
 // Box vertex and edge numbering:
 //
@@ -15,6 +21,26 @@ use crate::math::{Mat22, Vec2, sign_nonzero};
 //   v3 ------ v4
 //        e3#
 
+/// Bias toward shape A's faces when choosing the reference face, which
+/// keeps manifolds from flipping between faces of nearly equal separation.
+/// A face of B is only chosen if its separation beats `relative_tol` times
+/// A's plus `absolute_tol`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct FaceTolerance {
+    pub relative_tol: f32,
+    /// A length; boxes scale it by their half-width instead.
+    pub absolute_tol: f32,
+}
+
+impl Default for FaceTolerance {
+    fn default() -> Self {
+        Self {
+            relative_tol: 0.95,
+            absolute_tol: 0.01,
+        }
+    }
+}
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum Axis {
     FaceAX = 0,
@@ -23,40 +49,47 @@ pub enum Axis {
     FaceBY = 3,
 }
 
-#[repr(u8)]
-#[derive(Copy, Clone, Debug, Eq, PartialEq, Hash)]
-pub enum EdgeNumber {
-    NoEdge = 0,
-    Edge1 = 1,
-    Edge2 = 2,
-    Edge3 = 3,
-    Edge4 = 4,
-}
-
-impl Default for EdgeNumber {
-    fn default() -> Self {
-        EdgeNumber::NoEdge
-    }
+/// Edge feature id. `NoEdge` is zero; edge `k` of a shape (running from
+/// vertex `k` to vertex `k + 1`) is numbered `k + 1`, up to
+/// [`MAX_POLYGON_VERTICES`].
+///
+/// This used to be an enum of `NoEdge` and `Edge1`..`Edge4`. Those names
+/// remain as constants and still work as match patterns, but matches now
+/// need a `_` arm, and `edge as u8` becomes `edge.0`.
+#[repr(transparent)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq, Hash)]
+pub struct EdgeNumber(pub u8);
+
+#[allow(non_upper_case_globals)]
+impl EdgeNumber {
+    pub const NoEdge: EdgeNumber = EdgeNumber(0);
+    pub const Edge1: EdgeNumber = EdgeNumber(1);
+    pub const Edge2: EdgeNumber = EdgeNumber(2);
+    pub const Edge3: EdgeNumber = EdgeNumber(3);
+    pub const Edge4: EdgeNumber = EdgeNumber(4);
 }
 
 impl EdgeNumber {
     #[inline]
     pub const fn from_u8(v: u8) -> Self {
-        match v {
-            0 => EdgeNumber::NoEdge,
-            1 => EdgeNumber::Edge1,
-            2 => EdgeNumber::Edge2,
-            3 => EdgeNumber::Edge3,
-            4 => EdgeNumber::Edge4,
-            _ => EdgeNumber::NoEdge, // Defensive coding.
+        if v as usize <= MAX_POLYGON_VERTICES {
+            EdgeNumber(v)
+        } else {
+            EdgeNumber::NoEdge // Defensive coding.
         }
     }
+
+    /// Edge id of the zero-based polygon edge `k`.
+    #[inline]
+    pub const fn from_index(k: usize) -> Self {
+        EdgeNumber::from_u8((k + 1) as u8)
+    }
 }
 
 #[derive(Copy, Clone, Debug, Default)]
 pub struct ClipVertex {
-    v: Vec2,
-    fp: FeaturePair,
+    pub v: Vec2,
+    pub fp: FeaturePair,
 }
 
 pub fn flip(fp: &mut FeaturePair) {
@@ -159,6 +192,14 @@ pub fn compute_incident_edge(
     c[1].v = pos + rot * c[1].v;
 }
 
+/// Reverse the roles of A and B in already computed contacts.
+pub fn flip_contacts(contacts: &mut [Contact]) {
+    for c in contacts {
+        c.normal = -c.normal;
+        flip(&mut c.feature);
+    }
+}
+
 // The normal points from A to B
 pub fn collide(
     contacts: &mut [Contact; 2],
@@ -166,18 +207,160 @@ pub fn collide(
     body_b: BodyHandle,
     world: &World,
 ) -> usize {
-    let body_a = world.body(body_a);
-    let body_b = world.body(body_b);
+    collide_fixtures(
+        contacts,
+        world.body(body_a),
+        (0, 0),
+        world.body(body_b),
+        (0, 0),
+        world.config.face_tolerance,
+    )
+}
 
-    // Setup
-    let ha = 0.5 * body_a.width;
-    let hb = 0.5 * body_b.width;
+/// Narrow phase between a `(fixture, child)` of body A and one of body B
+/// (see [`Shape::child`]). The normal points from A to B.
+pub fn collide_fixtures(
+    contacts: &mut [Contact; 2],
+    body_a: &Body,
+    (fixture_a, child_a): (usize, usize),
+    body_b: &Body,
+    (fixture_b, child_b): (usize, usize),
+    tol: FaceTolerance,
+) -> usize {
+    collide_shapes(
+        contacts,
+        &body_a.fixtures[fixture_a].shape.child(child_a),
+        &body_a.fixture_transform(fixture_a),
+        &body_b.fixtures[fixture_b].shape.child(child_b),
+        &body_b.fixture_transform(fixture_b),
+        tol,
+    )
+}
 
-    let pos_a = body_a.position;
-    let pos_b = body_b.position;
+/// Narrow phase for two posed shapes. The normal points from A to B.
+///
+/// Chains must be split into their edges first with [`Shape::child`].
+pub fn collide_shapes(
+    contacts: &mut [Contact; 2],
+    shape_a: &Shape,
+    xf_a: &Transform,
+    shape_b: &Shape,
+    xf_b: &Transform,
+    tol: FaceTolerance,
+) -> usize {
+    match (shape_a, shape_b) {
+        (Shape::Chain(_), _) | (_, Shape::Chain(_)) => {
+            unreachable!("chains collide through their child edges")
+        }
+        (Shape::Box { width: wa }, Shape::Box { width: wb }) => {
+            collide_boxes(contacts, 0.5 * *wa, xf_a, 0.5 * *wb, xf_b, tol)
+        }
+        (Shape::Circle { radius: ra }, Shape::Circle { radius: rb }) => {
+            collide_circles(contacts, *ra, xf_a, *rb, xf_b)
+        }
+        // Segments and chains only go on static and kinematic bodies, which
+        // never collide with each other.
+        (Shape::Segment(_), Shape::Segment(_)) => 0,
+        (Shape::Segment(segment), Shape::Circle { radius }) => {
+            collide_segment_and_circle(contacts, segment, xf_a, *radius, xf_b)
+        }
+        (Shape::Segment(segment), _) => {
+            collide_segment_and_polygon(contacts, segment, xf_a, &as_polygon(shape_b), xf_b)
+        }
+        (_, Shape::Segment(_)) => {
+            let n = collide_shapes(contacts, shape_b, xf_b, shape_a, xf_a, tol);
+            flip_contacts(&mut contacts[..n]);
+            n
+        }
+        (
+            Shape::Capsule {
+                center1,
+                center2,
+                radius: ra,
+            },
+            Shape::Circle { radius: rb },
+        ) => collide_capsule_and_circle(contacts, *center1, *center2, *ra, xf_a, *rb, xf_b),
+        (Shape::Circle { .. }, _) => {
+            let n = collide_shapes(contacts, shape_b, xf_b, shape_a, xf_a, tol);
+            flip_contacts(&mut contacts[..n]);
+            n
+        }
+        (_, Shape::Circle { radius }) => {
+            collide_polygon_and_circle(contacts, &as_polygon(shape_a), xf_a, *radius, xf_b)
+        }
+        (
+            Shape::Capsule {
+                center1: a1,
+                center2: a2,
+                radius: ra,
+            },
+            Shape::Capsule {
+                center1: b1,
+                center2: b2,
+                radius: rb,
+            },
+        ) => collide_capsules(contacts, (*a1, *a2, *ra), xf_a, (*b1, *b2, *rb), xf_b, tol),
+        (
+            _,
+            Shape::Capsule {
+                center1,
+                center2,
+                radius,
+            },
+        ) => collide_polygon_and_capsule(
+            contacts,
+            &as_polygon(shape_a),
+            xf_a,
+            (*center1, *center2, *radius),
+            xf_b,
+            tol,
+        ),
+        (Shape::Capsule { .. }, _) => {
+            let n = collide_shapes(contacts, shape_b, xf_b, shape_a, xf_a, tol);
+            flip_contacts(&mut contacts[..n]);
+            n
+        }
+        _ => collide_polygons(
+            contacts,
+            &as_polygon(shape_a),
+            xf_a,
+            &as_polygon(shape_b),
+            xf_b,
+            tol,
+        ),
+    }
+}
 
-    let rot_a = Mat22::from_angle(body_a.rotation);
-    let rot_b = Mat22::from_angle(body_b.rotation);
+fn as_polygon(shape: &Shape) -> Polygon {
+    match *shape {
+        Shape::Box { width } => Polygon::make_box(0.5 * width),
+        Shape::Polygon(poly) => poly,
+        Shape::Capsule {
+            center1,
+            center2,
+            radius,
+        } => Polygon::make_capsule(center1, center2, radius),
+        Shape::Circle { .. } | Shape::Segment(_) | Shape::Chain(_) => {
+            unreachable!("shape is not a solid polygon")
+        }
+    }
+}
+
+/// Box-Box SAT with reference/incident face clipping (Box2D-Lite).
+pub fn collide_boxes(
+    contacts: &mut [Contact; 2],
+    ha: Vec2,
+    xf_a: &Transform,
+    hb: Vec2,
+    xf_b: &Transform,
+    tol: FaceTolerance,
+) -> usize {
+    // Setup
+    let pos_a = xf_a.position;
+    let pos_b = xf_b.position;
+
+    let rot_a = xf_a.rotation;
+    let rot_b = xf_b.rotation;
 
     let rot_at = rot_a.transpose();
     let rot_bt = rot_b.transpose();
@@ -214,8 +397,10 @@ pub fn collide(
     let mut best_sep = face_a.x;
     let mut normal = if da.x > 0.0 { rot_a.col1 } else { -rot_a.col1 };
 
-    let relative_tol: f32 = 0.95;
-    let absolute_tol: f32 = 0.01;
+    let FaceTolerance {
+        relative_tol,
+        absolute_tol,
+    } = tol;
 
     if face_a.y > relative_tol * best_sep + absolute_tol * ha.y {
         axis = Axis::FaceAY;
@@ -333,16 +518,16 @@ pub fn collide(
     // Now clipPoints2 contains the clipping points.
     // Due to roundoff, it is possible that clipping removes all points.
     let mut num_contacts: usize = 0;
-    for i in 0..2 {
-        let sep = front_normal.dot(clip_points2[i].v) - front;
+    for cp in &clip_points2 {
+        let sep = front_normal.dot(cp.v) - front;
 
         if sep <= 0.0 {
             let contact = &mut contacts[num_contacts];
             contact.separation = sep;
             contact.normal = normal;
             // slide contact point onto reference face (easy to cull)
-            contact.position = clip_points2[i].v - sep * front_normal;
-            contact.feature = clip_points2[i].fp;
+            contact.position = cp.v - sep * front_normal;
+            contact.feature = cp.fp;
             if axis == Axis::FaceBX || axis == Axis::FaceBY {
                 flip(&mut contacts[num_contacts].feature);
             }
@@ -365,6 +550,7 @@ mod tests {
         assert_eq!(EdgeNumber::from_u8(2), EdgeNumber::Edge2);
         assert_eq!(EdgeNumber::from_u8(3), EdgeNumber::Edge3);
         assert_eq!(EdgeNumber::from_u8(4), EdgeNumber::Edge4);
+        assert_eq!(EdgeNumber::from_index(5), EdgeNumber(6));
         assert_eq!(EdgeNumber::from_u8(200), EdgeNumber::NoEdge);
     }
 
@@ -409,4 +595,28 @@ mod tests {
         assert_relative_eq!(v_out[1].v.x, 0.5, epsilon = 1e-6);
         assert_relative_eq!(v_out[1].v.y, 0.0, epsilon = 1e-6);
     }
+
+    #[test]
+    fn collide_shapes_circle_then_box_flips_normal() {
+        let mut contacts = [Contact::default(); 2];
+        let circle = Shape::Circle { radius: 0.5 };
+        let bx = Shape::Box {
+            width: Vec2::new(2.0, 2.0),
+        };
+        let xf_circle = Transform::new(Vec2::new(0.0, 1.4), 0.0);
+        let xf_box = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+
+        let n = collide_shapes(
+            &mut contacts,
+            &circle,
+            &xf_circle,
+            &bx,
+            &xf_box,
+            FaceTolerance::default(),
+        );
+        assert_eq!(n, 1);
+        // Points from the circle (A) down into the box (B).
+        assert_relative_eq!(contacts[0].normal.y, -1.0, epsilon = 1e-6);
+        assert_eq!(contacts[0].feature.in_edge2, EdgeNumber::Edge1);
+    }
 }
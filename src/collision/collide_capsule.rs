@@ -0,0 +1,209 @@
+use crate::collision::arbiter::Contact;
+use crate::collision::collide_circle::collide_circles;
+use crate::collision::collide_polygon::collide_polygons;
+use crate::collision::{EdgeNumber, FaceTolerance, FeaturePair, Polygon};
+use crate::math::{Transform, Vec2};
+
+/// Closest points between segments `p1-q1` and `p2-q2`.
+/// Returns `(fraction1, fraction2, closest1, closest2)`.
+pub fn segment_distance(p1: Vec2, q1: Vec2, p2: Vec2, q2: Vec2) -> (f32, f32, Vec2, Vec2) {
+    let d1 = q1 - p1;
+    let d2 = q2 - p2;
+    let r = p1 - p2;
+    let dd1 = d1.dot(d1);
+    let dd2 = d2.dot(d2);
+    let rd1 = r.dot(d1);
+    let rd2 = r.dot(d2);
+
+    let eps_sqr = f32::EPSILON * f32::EPSILON;
+
+    let (f1, f2) = if dd1 < eps_sqr || dd2 < eps_sqr {
+        // One or both segments are points.
+        if dd1 >= eps_sqr {
+            ((-rd1 / dd1).clamp(0.0, 1.0), 0.0)
+        } else if dd2 >= eps_sqr {
+            (0.0, (rd2 / dd2).clamp(0.0, 1.0))
+        } else {
+            (0.0, 0.0)
+        }
+    } else {
+        // Non-degenerate segments.
+        let d12 = d1.dot(d2);
+        let denom = dd1 * dd2 - d12 * d12;
+
+        // Parallel segments pick the start of segment 1.
+        let mut f1 = if denom != 0.0 {
+            ((d12 * rd2 - rd1 * dd2) / denom).clamp(0.0, 1.0)
+        } else {
+            0.0
+        };
+        let mut f2 = (d12 * f1 + rd2) / dd2;
+
+        // Clamp segment 2 and recompute segment 1.
+        if f2 < 0.0 {
+            f2 = 0.0;
+            f1 = (-rd1 / dd1).clamp(0.0, 1.0);
+        } else if f2 > 1.0 {
+            f2 = 1.0;
+            f1 = ((d12 - rd1) / dd1).clamp(0.0, 1.0);
+        }
+        (f1, f2)
+    };
+
+    (f1, f2, p1 + f1 * d1, p2 + f2 * d2)
+}
+
+/// Capsule-Circle. The normal points from A (capsule) to B (circle).
+pub fn collide_capsule_and_circle(
+    contacts: &mut [Contact; 2],
+    center1: Vec2,
+    center2: Vec2,
+    radius_a: f32,
+    xf_a: &Transform,
+    radius_b: f32,
+    xf_b: &Transform,
+) -> usize {
+    // Closest point on the capsule segment to the circle center.
+    let p = xf_a.apply_inverse(xf_b.position);
+    let e = center2 - center1;
+    let ee = e.dot(e);
+    let t = if ee > f32::EPSILON * f32::EPSILON {
+        ((p - center1).dot(e) / ee).clamp(0.0, 1.0)
+    } else {
+        0.0
+    };
+    let closest = Transform {
+        position: xf_a.apply(center1 + t * e),
+        rotation: xf_a.rotation,
+    };
+
+    let n = collide_circles(contacts, radius_a, &closest, radius_b, xf_b);
+    if n > 0 {
+        // Number features like a two-vertex polygon (edge 1 runs from center1
+        // to center2, edge 2 back) so warm starting survives sliding off a cap.
+        let (in_edge, out_edge) = if t == 0.0 {
+            (EdgeNumber::Edge2, EdgeNumber::Edge1)
+        } else if t == 1.0 {
+            (EdgeNumber::Edge1, EdgeNumber::Edge2)
+        } else if e.cross(p - center1) <= 0.0 {
+            (EdgeNumber::Edge1, EdgeNumber::NoEdge)
+        } else {
+            (EdgeNumber::Edge2, EdgeNumber::NoEdge)
+        };
+        contacts[0].feature =
+            FeaturePair::new(in_edge, out_edge, EdgeNumber::NoEdge, EdgeNumber::NoEdge);
+    }
+    n
+}
+
+/// Capsule-Capsule. The normal points from A to B.
+pub fn collide_capsules(
+    contacts: &mut [Contact; 2],
+    capsule_a: (Vec2, Vec2, f32),
+    xf_a: &Transform,
+    capsule_b: (Vec2, Vec2, f32),
+    xf_b: &Transform,
+    tol: FaceTolerance,
+) -> usize {
+    let poly_a = Polygon::make_capsule(capsule_a.0, capsule_a.1, capsule_a.2);
+    let poly_b = Polygon::make_capsule(capsule_b.0, capsule_b.1, capsule_b.2);
+    collide_polygons(contacts, &poly_a, xf_a, &poly_b, xf_b, tol)
+}
+
+/// Polygon-Capsule. The normal points from A (polygon) to B (capsule).
+pub fn collide_polygon_and_capsule(
+    contacts: &mut [Contact; 2],
+    poly_a: &Polygon,
+    xf_a: &Transform,
+    capsule_b: (Vec2, Vec2, f32),
+    xf_b: &Transform,
+    tol: FaceTolerance,
+) -> usize {
+    let poly_b = Polygon::make_capsule(capsule_b.0, capsule_b.1, capsule_b.2);
+    collide_polygons(contacts, poly_a, xf_a, &poly_b, xf_b, tol)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn segment_distance_crossing_and_parallel() {
+        let (_, _, c1, c2) = segment_distance(
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+            Vec2::new(0.0, 2.0),
+        );
+        assert_relative_eq!(c1.x, 0.0, epsilon = 1e-6);
+        assert_relative_eq!((c2 - c1).length(), 1.0, epsilon = 1e-6);
+
+        let (f1, f2, _, _) = segment_distance(
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(3.0, 1.0),
+            Vec2::new(4.0, 1.0),
+        );
+        assert_relative_eq!(f1, 1.0);
+        assert_relative_eq!(f2, 0.0);
+    }
+
+    #[test]
+    fn capsule_and_circle_side_and_cap() {
+        let mut contacts = [Contact::default(); 2];
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let (c1, c2) = (Vec2::new(-1.0, 0.0), Vec2::new(1.0, 0.0));
+
+        let side = Transform::new(Vec2::new(0.3, 0.9), 0.0);
+        let n = collide_capsule_and_circle(&mut contacts, c1, c2, 0.5, &xf_a, 0.5, &side);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].normal.y, 1.0, epsilon = 1e-6);
+        assert_relative_eq!(contacts[0].separation, -0.1, epsilon = 1e-5);
+
+        let cap = Transform::new(Vec2::new(1.9, 0.0), 0.0);
+        let n = collide_capsule_and_circle(&mut contacts, c1, c2, 0.5, &xf_a, 0.5, &cap);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].normal.x, 1.0, epsilon = 1e-6);
+        assert_ne!(contacts[0].feature, FeaturePair::default());
+    }
+
+    #[test]
+    fn parallel_capsules_get_two_contacts() {
+        let tol = FaceTolerance::default();
+        let mut contacts = [Contact::default(); 2];
+        let capsule = (Vec2::new(-1.0, 0.0), Vec2::new(1.0, 0.0), 0.25);
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let xf_b = Transform::new(Vec2::new(0.5, 0.45), 0.0);
+
+        let n = collide_capsules(&mut contacts, capsule, &xf_a, capsule, &xf_b, tol);
+        assert_eq!(n, 2);
+        for c in &contacts[..n] {
+            assert_relative_eq!(c.normal.y, 1.0, epsilon = 1e-5);
+            assert_relative_eq!(c.separation, -0.05, epsilon = 1e-5);
+        }
+    }
+
+    #[test]
+    fn capsule_end_near_box_corner_uses_rounded_distance() {
+        let tol = FaceTolerance::default();
+        let mut contacts = [Contact::default(); 2];
+        let bx = Polygon::make_box(Vec2::new(0.5, 0.5));
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let capsule = (Vec2::new(0.0, 0.0), Vec2::new(1.0, 0.0), 0.25);
+
+        // Capsule end sits diagonally off the (+,+) corner, outside its radius
+        // even though both face tests overlap.
+        let xf_b = Transform::new(Vec2::new(0.7, 0.7), core::f32::consts::FRAC_PI_4);
+        assert_eq!(
+            collide_polygon_and_capsule(&mut contacts, &bx, &xf_a, capsule, &xf_b, tol),
+            0
+        );
+
+        let xf_b = Transform::new(Vec2::new(0.6, 0.6), core::f32::consts::FRAC_PI_4);
+        let n = collide_polygon_and_capsule(&mut contacts, &bx, &xf_a, capsule, &xf_b, tol);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].normal.x, contacts[0].normal.y, epsilon = 1e-4);
+        assert!(contacts[0].separation < 0.0);
+    }
+}
@@ -0,0 +1,208 @@
+use crate::collision::arbiter::Contact;
+use crate::collision::{EdgeNumber, FeaturePair, Polygon};
+use crate::math::{Transform, Vec2};
+
+/// Circle-Circle. The normal points from A to B.
+pub fn collide_circles(
+    contacts: &mut [Contact; 2],
+    radius_a: f32,
+    xf_a: &Transform,
+    radius_b: f32,
+    xf_b: &Transform,
+) -> usize {
+    let d = xf_b.position - xf_a.position;
+    let dist_sqr = d.length_squared();
+    let r = radius_a + radius_b;
+    if dist_sqr > r * r {
+        return 0;
+    }
+
+    let dist = dist_sqr.sqrt();
+    let normal = if dist > f32::EPSILON {
+        (1.0 / dist) * d
+    } else {
+        // Concentric: any direction works, pick a stable one.
+        Vec2::new(1.0, 0.0)
+    };
+
+    let c_a = xf_a.position + radius_a * normal;
+    let c_b = xf_b.position - radius_b * normal;
+
+    let contact = &mut contacts[0];
+    contact.separation = dist - r;
+    contact.normal = normal;
+    contact.position = 0.5 * (c_a + c_b);
+    contact.feature = FeaturePair::default();
+    1
+}
+
+/// Box-Circle, `h` is the box half extents. The normal points from A (box) to B (circle).
+pub fn collide_box_and_circle(
+    contacts: &mut [Contact; 2],
+    h: Vec2,
+    xf_a: &Transform,
+    radius: f32,
+    xf_b: &Transform,
+) -> usize {
+    collide_polygon_and_circle(contacts, &Polygon::make_box(h), xf_a, radius, xf_b)
+}
+
+/// Polygon-Circle. The normal points from A (polygon) to B (circle).
+pub fn collide_polygon_and_circle(
+    contacts: &mut [Contact; 2],
+    poly: &Polygon,
+    xf_a: &Transform,
+    radius: f32,
+    xf_b: &Transform,
+) -> usize {
+    // Circle center in the polygon frame.
+    let c = xf_a.apply_inverse(xf_b.position);
+    let count = poly.count;
+
+    // Fold a rounded polygon's skin into the circle.
+    let total = radius + poly.radius;
+
+    // Find the face of minimum penetration.
+    let mut face = 0;
+    let mut separation = f32::MIN;
+    for k in 0..count {
+        let s = poly.normals[k].dot(c - poly.vertices[k]);
+        if s > total {
+            return 0;
+        }
+        if s > separation {
+            separation = s;
+            face = k;
+        }
+    }
+
+    let v1 = poly.vertices[face];
+    let v2 = poly.vertices[(face + 1) % count];
+    let face_edge = EdgeNumber::from_index(face);
+    let prev_edge = EdgeNumber::from_index((face + count - 1) % count);
+    let next_edge = EdgeNumber::from_index((face + 1) % count);
+
+    // Local normal, closest point on the polygon and the feature id.
+    let (normal, point_a, sep, feature) = if separation < f32::EPSILON {
+        // Center is inside the polygon.
+        let n = poly.normals[face];
+        let fp = FeaturePair::new(
+            face_edge,
+            EdgeNumber::NoEdge,
+            EdgeNumber::NoEdge,
+            EdgeNumber::NoEdge,
+        );
+        (n, c - separation * n, separation - total, fp)
+    } else if (c - v1).dot(v2 - v1) <= 0.0 {
+        // Vertex region of v1.
+        let d = c - v1;
+        if d.length_squared() > total * total {
+            return 0;
+        }
+        let fp = FeaturePair::new(prev_edge, face_edge, EdgeNumber::NoEdge, EdgeNumber::NoEdge);
+        (d.normalize(), v1, d.length() - total, fp)
+    } else if (c - v2).dot(v1 - v2) <= 0.0 {
+        // Vertex region of v2.
+        let d = c - v2;
+        if d.length_squared() > total * total {
+            return 0;
+        }
+        let fp = FeaturePair::new(face_edge, next_edge, EdgeNumber::NoEdge, EdgeNumber::NoEdge);
+        (d.normalize(), v2, d.length() - total, fp)
+    } else {
+        // Face region.
+        let n = poly.normals[face];
+        let fp = FeaturePair::new(
+            face_edge,
+            EdgeNumber::NoEdge,
+            EdgeNumber::NoEdge,
+            EdgeNumber::NoEdge,
+        );
+        (n, c - separation * n, separation - total, fp)
+    };
+
+    let point_a = point_a + poly.radius * normal;
+    let point_b = c - radius * normal;
+
+    let contact = &mut contacts[0];
+    contact.separation = sep;
+    contact.normal = xf_a.rotation * normal;
+    contact.position = xf_a.apply(0.5 * (point_a + point_b));
+    contact.feature = feature;
+    1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn circles_apart_do_not_collide() {
+        let mut contacts = [Contact::default(); 2];
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let xf_b = Transform::new(Vec2::new(3.0, 0.0), 0.0);
+        assert_eq!(collide_circles(&mut contacts, 1.0, &xf_a, 1.0, &xf_b), 0);
+    }
+
+    #[test]
+    fn circles_overlapping_report_depth_and_normal() {
+        let mut contacts = [Contact::default(); 2];
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let xf_b = Transform::new(Vec2::new(0.0, 1.5), 0.0);
+        let n = collide_circles(&mut contacts, 1.0, &xf_a, 1.0, &xf_b);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].separation, -0.5, epsilon = 1e-6);
+        assert_relative_eq!(contacts[0].normal.y, 1.0, epsilon = 1e-6);
+        assert_relative_eq!(contacts[0].position.y, 0.75, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn box_and_circle_face_contact() {
+        let mut contacts = [Contact::default(); 2];
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let xf_b = Transform::new(Vec2::new(0.0, 1.4), 0.0);
+        let n = collide_box_and_circle(&mut contacts, Vec2::new(1.0, 1.0), &xf_a, 0.5, &xf_b);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].separation, -0.1, epsilon = 1e-5);
+        assert_relative_eq!(contacts[0].normal.y, 1.0, epsilon = 1e-6);
+        assert_eq!(contacts[0].feature.in_edge1, EdgeNumber::Edge1);
+    }
+
+    #[test]
+    fn box_and_circle_vertex_region() {
+        let mut contacts = [Contact::default(); 2];
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+
+        // Diagonal from the (+,+) corner, just out of reach.
+        let xf_b = Transform::new(Vec2::new(1.4, 1.4), 0.0);
+        let n = collide_box_and_circle(&mut contacts, Vec2::new(1.0, 1.0), &xf_a, 0.5, &xf_b);
+        assert_eq!(n, 0);
+
+        let xf_b = Transform::new(Vec2::new(1.3, 1.3), 0.0);
+        let n = collide_box_and_circle(&mut contacts, Vec2::new(1.0, 1.0), &xf_a, 0.5, &xf_b);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].normal.x, contacts[0].normal.y, epsilon = 1e-5);
+        assert!(contacts[0].separation < 0.0);
+    }
+
+    #[test]
+    fn polygon_and_circle_touches_slanted_face() {
+        let mut contacts = [Contact::default(); 2];
+        let tri = Polygon::new(&[
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 1.0),
+        ]);
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+
+        // Circle resting against the right slope, slightly overlapping.
+        let n_face = Vec2::new(1.0, 1.0).normalize();
+        let xf_b = Transform::new(Vec2::new(0.5, 0.5) + 0.45 * n_face, 0.0);
+        let n = collide_polygon_and_circle(&mut contacts, &tri, &xf_a, 0.5, &xf_b);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].normal.x, n_face.x, epsilon = 1e-5);
+        assert_relative_eq!(contacts[0].normal.y, n_face.y, epsilon = 1e-5);
+        assert_relative_eq!(contacts[0].separation, -0.05, epsilon = 1e-5);
+    }
+}
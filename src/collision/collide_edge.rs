@@ -0,0 +1,428 @@
+use crate::collision::arbiter::Contact;
+use crate::collision::collide::{ClipVertex, clip_segment_to_line, flip};
+use crate::collision::{EdgeNumber, FeaturePair, Polygon, Segment};
+use crate::math::{Transform, Vec2};
+
+// Segment features follow the two-vertex polygon numbering: edge 1 runs from
+// vertex1 to vertex2 (right side), edge 2 back from vertex2 to vertex1.
+
+/// Segment-Circle. The normal points from A (segment) to B (circle).
+pub fn collide_segment_and_circle(
+    contacts: &mut [Contact; 2],
+    segment: &Segment,
+    xf_a: &Transform,
+    radius: f32,
+    xf_b: &Transform,
+) -> usize {
+    // Circle center in the segment frame.
+    let q = xf_a.apply_inverse(xf_b.position);
+
+    let a = segment.vertex1;
+    let b = segment.vertex2;
+    let e = b - a;
+
+    // Right-hand normal; one-sided segments ignore circles behind them.
+    let n = Vec2::cross_vec_scalar(e, 1.0);
+    let offset = n.dot(q - a);
+    if segment.one_sided && offset < 0.0 {
+        return 0;
+    }
+
+    // Barycentric coordinates of q along the segment.
+    let u = e.dot(b - q);
+    let v = e.dot(q - a);
+
+    let face_normal = if offset < 0.0 { -n } else { n }.normalize();
+    let face_edge = if offset < 0.0 {
+        EdgeNumber::Edge2
+    } else {
+        EdgeNumber::Edge1
+    };
+
+    let (p, feature) = if v <= 0.0 {
+        // Region of vertex1.
+        if segment.one_sided {
+            // Leave it to the previous segment if q projects onto it.
+            let e0 = a - segment.vertex0;
+            if e0.dot(a - q) > 0.0 {
+                return 0;
+            }
+        }
+        (
+            a,
+            FeaturePair::new(
+                EdgeNumber::Edge2,
+                EdgeNumber::Edge1,
+                EdgeNumber::NoEdge,
+                EdgeNumber::NoEdge,
+            ),
+        )
+    } else if u <= 0.0 {
+        // Region of vertex2.
+        if segment.one_sided {
+            // Leave it to the next segment if q projects onto it.
+            let e2 = segment.vertex3 - b;
+            if e2.dot(q - b) > 0.0 {
+                return 0;
+            }
+        }
+        (
+            b,
+            FeaturePair::new(
+                EdgeNumber::Edge1,
+                EdgeNumber::Edge2,
+                EdgeNumber::NoEdge,
+                EdgeNumber::NoEdge,
+            ),
+        )
+    } else {
+        // Face region.
+        let p = (1.0 / e.dot(e)) * (u * a + v * b);
+        (
+            p,
+            FeaturePair::new(
+                face_edge,
+                EdgeNumber::NoEdge,
+                EdgeNumber::NoEdge,
+                EdgeNumber::NoEdge,
+            ),
+        )
+    };
+
+    let d = q - p;
+    if d.length_squared() > radius * radius {
+        return 0;
+    }
+
+    // Fall back to the face normal when the center is on the segment.
+    let normal = if d.length_squared() > f32::EPSILON * f32::EPSILON {
+        d.normalize()
+    } else {
+        face_normal
+    };
+
+    let contact = &mut contacts[0];
+    contact.separation = normal.dot(d) - radius;
+    contact.normal = xf_a.rotation * normal;
+    contact.position = xf_a.apply(0.5 * (p + (q - radius * normal)));
+    contact.feature = feature;
+    1
+}
+
+/// Segment-Polygon, including rounded polygons (capsules). One-sided segments
+/// use their ghost vertices to skip contacts that belong to a neighbour.
+/// The normal points from A (segment) to B (polygon).
+pub fn collide_segment_and_polygon(
+    contacts: &mut [Contact; 2],
+    segment: &Segment,
+    xf_a: &Transform,
+    poly_b: &Polygon,
+    xf_b: &Transform,
+) -> usize {
+    // Polygon B in the segment frame.
+    let mut poly = *poly_b;
+    let rot = xf_a.rotation.transpose() * xf_b.rotation;
+    let mut centroid = Vec2::new(0.0, 0.0);
+    for k in 0..poly.count {
+        poly.vertices[k] = xf_a.apply_inverse(xf_b.apply(poly_b.vertices[k]));
+        poly.normals[k] = rot * poly_b.normals[k];
+        centroid += poly.vertices[k];
+    }
+    centroid = (1.0 / poly.count as f32) * centroid;
+
+    let v1 = segment.vertex1;
+    let v2 = segment.vertex2;
+    let edge1 = (v2 - v1).normalize();
+    let normal1 = Vec2::cross_vec_scalar(edge1, 1.0);
+
+    if segment.one_sided && normal1.dot(centroid - v1) < 0.0 {
+        return 0;
+    }
+
+    let radius = poly.radius;
+
+    // Segment axes: the side with the least overlap.
+    let mut edge_separation = f32::MIN;
+    let mut edge_normal = normal1;
+    for n in [normal1, -normal1] {
+        let s = poly
+            .vertices()
+            .iter()
+            .map(|v| n.dot(*v - v1))
+            .fold(f32::MAX, f32::min);
+        if s > edge_separation {
+            edge_separation = s;
+            edge_normal = n;
+        }
+    }
+    if edge_separation > radius {
+        return 0;
+    }
+
+    // Polygon axes.
+    let mut poly_separation = f32::MIN;
+    let mut poly_index = 0;
+    for k in 0..poly.count {
+        let n = -poly.normals[k];
+        let s = n
+            .dot(poly.vertices[k] - v1)
+            .min(n.dot(poly.vertices[k] - v2));
+        if s > poly_separation {
+            poly_separation = s;
+            poly_index = k;
+        }
+    }
+    if poly_separation > radius {
+        return 0;
+    }
+
+    // Hysteresis keeps the reference feature from flickering.
+    let relative_tol: f32 = 0.98;
+    let absolute_tol: f32 = 0.001;
+
+    let mut use_polygon =
+        poly_separation - radius > relative_tol * (edge_separation - radius) + absolute_tol;
+
+    if segment.one_sided {
+        // Smooth collision: reject normals that point into a neighbour's
+        // region and snap to the segment normal at concave vertices.
+        let axis_normal = if use_polygon {
+            -poly.normals[poly_index]
+        } else {
+            edge_normal
+        };
+
+        let edge0 = (v1 - segment.vertex0).normalize();
+        let normal0 = Vec2::cross_vec_scalar(edge0, 1.0);
+        let convex1 = edge0.cross(edge1) >= 0.0;
+
+        let edge2 = (segment.vertex3 - v2).normalize();
+        let normal2 = Vec2::cross_vec_scalar(edge2, 1.0);
+        let convex2 = edge1.cross(edge2) >= 0.0;
+
+        let sin_tol = 0.1;
+        if axis_normal.dot(edge1) <= 0.0 {
+            if convex1 {
+                if axis_normal.cross(normal0) > sin_tol {
+                    return 0;
+                }
+            } else {
+                use_polygon = false;
+            }
+        } else if convex2 {
+            if normal2.cross(axis_normal) > sin_tol {
+                return 0;
+            }
+        } else {
+            use_polygon = false;
+        }
+    }
+
+    let mut incident = [ClipVertex::default(); 2];
+    let (ref_v1, ref_v2, ref_normal, ref_radius, neg_edge, pos_edge) = if use_polygon {
+        // Polygon face is the reference, the segment is incident.
+        incident[0].v = v2;
+        incident[0].fp.in_edge2 = EdgeNumber::Edge1;
+        incident[0].fp.out_edge2 = EdgeNumber::Edge2;
+        incident[1].v = v1;
+        incident[1].fp.in_edge2 = EdgeNumber::Edge2;
+        incident[1].fp.out_edge2 = EdgeNumber::Edge1;
+
+        let count = poly.count;
+        let i1 = poly_index;
+        let i2 = (i1 + 1) % count;
+        (
+            poly.vertices[i1],
+            poly.vertices[i2],
+            poly.normals[i1],
+            radius,
+            EdgeNumber::from_index((i1 + count - 1) % count),
+            EdgeNumber::from_index(i2),
+        )
+    } else {
+        // Segment is the reference; find the most anti-parallel polygon edge.
+        let mut i1 = 0;
+        let mut min_dot = f32::MAX;
+        for k in 0..poly.count {
+            let dot = edge_normal.dot(poly.normals[k]);
+            if dot < min_dot {
+                min_dot = dot;
+                i1 = k;
+            }
+        }
+        let count = poly.count;
+        let i2 = (i1 + 1) % count;
+
+        incident[0].v = poly.vertices[i1];
+        incident[0].fp.in_edge2 = EdgeNumber::from_index((i1 + count - 1) % count);
+        incident[0].fp.out_edge2 = EdgeNumber::from_index(i1);
+        incident[1].v = poly.vertices[i2];
+        incident[1].fp.in_edge2 = EdgeNumber::from_index(i1);
+        incident[1].fp.out_edge2 = EdgeNumber::from_index(i2);
+
+        // Both side planes of a segment face are bounded by the other face.
+        let side_edge = if edge_normal == normal1 {
+            EdgeNumber::Edge2
+        } else {
+            EdgeNumber::Edge1
+        };
+        (v1, v2, edge_normal, 0.0, side_edge, side_edge)
+    };
+
+    let tangent = (ref_v2 - ref_v1).normalize();
+
+    let mut clip_points1 = [ClipVertex::default(); 2];
+    let mut clip_points2 = [ClipVertex::default(); 2];
+
+    let np = clip_segment_to_line(
+        &mut clip_points1,
+        &incident,
+        -tangent,
+        -tangent.dot(ref_v1),
+        neg_edge,
+    );
+    if np < 2 {
+        return 0;
+    }
+
+    let np = clip_segment_to_line(
+        &mut clip_points2,
+        &clip_points1,
+        tangent,
+        tangent.dot(ref_v2),
+        pos_edge,
+    );
+    if np < 2 {
+        return 0;
+    }
+
+    let normal = if use_polygon { -ref_normal } else { ref_normal };
+
+    let mut num_contacts: usize = 0;
+    for cp in &clip_points2 {
+        let core_sep = ref_normal.dot(cp.v - ref_v1);
+        let sep = core_sep - radius;
+
+        if sep <= 0.0 {
+            let contact = &mut contacts[num_contacts];
+            contact.separation = sep;
+            contact.normal = xf_a.rotation * normal;
+            // slide contact point onto reference surface (easy to cull)
+            contact.position = xf_a.apply(cp.v - (core_sep - ref_radius) * ref_normal);
+            contact.feature = cp.fp;
+            if use_polygon {
+                flip(&mut contact.feature);
+            }
+            num_contacts += 1;
+        }
+    }
+
+    num_contacts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn ground_segment(one_sided: bool) -> Segment {
+        if one_sided {
+            Segment::one_sided(
+                Vec2::new(-6.0, 0.0),
+                Vec2::new(-5.0, 0.0),
+                Vec2::new(5.0, 0.0),
+                Vec2::new(6.0, 0.0),
+            )
+        } else {
+            Segment::two_sided(Vec2::new(-5.0, 0.0), Vec2::new(5.0, 0.0))
+        }
+    }
+
+    #[test]
+    fn circle_on_top_of_segment() {
+        let mut contacts = [Contact::default(); 2];
+        // Edge 1 runs right to left, so its right side faces up.
+        let seg = Segment::two_sided(Vec2::new(5.0, 0.0), Vec2::new(-5.0, 0.0));
+        let xf_a = Transform::default();
+        let xf_b = Transform::new(Vec2::new(1.0, 0.4), 0.0);
+
+        let n = collide_segment_and_circle(&mut contacts, &seg, &xf_a, 0.5, &xf_b);
+        assert_eq!(n, 1);
+        assert_relative_eq!(contacts[0].normal.y, 1.0, epsilon = 1e-6);
+        assert_relative_eq!(contacts[0].separation, -0.1, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn one_sided_segment_ignores_circle_behind() {
+        let mut contacts = [Contact::default(); 2];
+        let seg = ground_segment(true);
+        let xf_a = Transform::default();
+
+        // Right side of a left-to-right segment is below it.
+        let below = Transform::new(Vec2::new(0.0, -0.4), 0.0);
+        assert_eq!(
+            collide_segment_and_circle(&mut contacts, &seg, &xf_a, 0.5, &below),
+            1
+        );
+
+        let above = Transform::new(Vec2::new(0.0, 0.4), 0.0);
+        assert_eq!(
+            collide_segment_and_circle(&mut contacts, &seg, &xf_a, 0.5, &above),
+            0
+        );
+    }
+
+    #[test]
+    fn box_on_two_sided_segment_gets_two_contacts() {
+        let mut contacts = [Contact::default(); 2];
+        let seg = ground_segment(false);
+        let bx = Polygon::make_box(Vec2::new(0.5, 0.5));
+        let xf_a = Transform::default();
+        let xf_b = Transform::new(Vec2::new(0.0, 0.45), 0.0);
+
+        let n = collide_segment_and_polygon(&mut contacts, &seg, &xf_a, &bx, &xf_b);
+        assert_eq!(n, 2);
+        for c in &contacts[..n] {
+            assert_relative_eq!(c.normal.y, 1.0, epsilon = 1e-6);
+            assert_relative_eq!(c.separation, -0.05, epsilon = 1e-5);
+            assert_relative_eq!(c.position.y, 0.0, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn one_sided_ghost_hands_vertex_region_to_neighbour() {
+        let mut contacts = [Contact::default(); 2];
+        // Right to left, so the collision side faces up. The neighbour
+        // continues flat past vertex2 at x=0.
+        let one_sided = Segment::one_sided(
+            Vec2::new(2.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(0.0, 0.0),
+            Vec2::new(-1.0, 0.0),
+        );
+        let two_sided = Segment::two_sided(one_sided.vertex1, one_sided.vertex2);
+        let xf_a = Transform::default();
+
+        // Circle past the end of this segment, over the neighbour.
+        let xf_b = Transform::new(Vec2::new(-0.2, 0.3), 0.0);
+        assert_eq!(
+            collide_segment_and_circle(&mut contacts, &two_sided, &xf_a, 0.5, &xf_b),
+            1
+        );
+        assert!(contacts[0].normal.x < 0.0);
+        assert_eq!(
+            collide_segment_and_circle(&mut contacts, &one_sided, &xf_a, 0.5, &xf_b),
+            0
+        );
+
+        // A box straddling the vertex still gets an upright normal.
+        let bx = Polygon::make_box(Vec2::new(0.5, 0.5));
+        let xf_b = Transform::new(Vec2::new(-0.4, 0.48), 0.0);
+        let n = collide_segment_and_polygon(&mut contacts, &one_sided, &xf_a, &bx, &xf_b);
+        assert_eq!(n, 2);
+        for c in &contacts[..n] {
+            assert_relative_eq!(c.normal.x, 0.0, epsilon = 1e-6);
+            assert_relative_eq!(c.normal.y, 1.0, epsilon = 1e-6);
+        }
+    }
+}
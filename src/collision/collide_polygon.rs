@@ -0,0 +1,295 @@
+use crate::collision::arbiter::Contact;
+use crate::collision::collide::{ClipVertex, clip_segment_to_line, flip};
+use crate::collision::collide_capsule::segment_distance;
+use crate::collision::{EdgeNumber, FaceTolerance, FeaturePair, Polygon};
+use crate::math::{Transform, Vec2};
+
+/// Find the edge of `poly1` with the largest separation from `poly2`.
+/// Returns `(edge_index, separation)`.
+pub fn find_max_separation(
+    poly1: &Polygon,
+    xf1: &Transform,
+    poly2: &Polygon,
+    xf2: &Transform,
+) -> (usize, f32) {
+    let mut best_index = 0;
+    let mut max_separation = f32::MIN;
+
+    for k in 0..poly1.count {
+        // Edge normal and vertex of poly1 in poly2's frame.
+        let n = xf2.rotation.transpose() * (xf1.rotation * poly1.normals[k]);
+        let v1 = xf2.apply_inverse(xf1.apply(poly1.vertices[k]));
+
+        // Deepest point of poly2 along -n.
+        let si = poly2
+            .vertices()
+            .iter()
+            .map(|v2| n.dot(*v2 - v1))
+            .fold(f32::MAX, f32::min);
+
+        if si > max_separation {
+            max_separation = si;
+            best_index = k;
+        }
+    }
+
+    (best_index, max_separation)
+}
+
+/// Incident edge of `poly2` for reference edge `edge1` of `poly1`, in world space.
+pub fn find_incident_edge(
+    c: &mut [ClipVertex; 2],
+    poly1: &Polygon,
+    xf1: &Transform,
+    edge1: usize,
+    poly2: &Polygon,
+    xf2: &Transform,
+) {
+    // Reference normal in poly2's frame.
+    let normal1 = xf2.rotation.transpose() * (xf1.rotation * poly1.normals[edge1]);
+
+    // The incident edge is the one most anti-parallel to the reference normal.
+    let mut index = 0;
+    let mut min_dot = f32::MAX;
+    for k in 0..poly2.count {
+        let dot = normal1.dot(poly2.normals[k]);
+        if dot < min_dot {
+            min_dot = dot;
+            index = k;
+        }
+    }
+
+    let count = poly2.count;
+    let i1 = index;
+    let i2 = (index + 1) % count;
+
+    c[0].v = xf2.apply(poly2.vertices[i1]);
+    c[0].fp.in_edge2 = EdgeNumber::from_index((i1 + count - 1) % count);
+    c[0].fp.out_edge2 = EdgeNumber::from_index(i1);
+
+    c[1].v = xf2.apply(poly2.vertices[i2]);
+    c[1].fp.in_edge2 = EdgeNumber::from_index(i1);
+    c[1].fp.out_edge2 = EdgeNumber::from_index(i2);
+}
+
+/// Polygon-Polygon SAT with reference/incident edge clipping. Rounded
+/// polygons (non-zero `radius`) are supported. The normal points from A to B.
+pub fn collide_polygons(
+    contacts: &mut [Contact; 2],
+    poly_a: &Polygon,
+    xf_a: &Transform,
+    poly_b: &Polygon,
+    xf_b: &Transform,
+    tol: FaceTolerance,
+) -> usize {
+    let radius = poly_a.radius + poly_b.radius;
+
+    let (edge_a, separation_a) = find_max_separation(poly_a, xf_a, poly_b, xf_b);
+    if separation_a > radius {
+        return 0;
+    }
+
+    let (edge_b, separation_b) = find_max_separation(poly_b, xf_b, poly_a, xf_a);
+    if separation_b > radius {
+        return 0;
+    }
+
+    // Same bias toward A as the box-box test, to keep the reference face stable.
+    let FaceTolerance {
+        relative_tol,
+        absolute_tol,
+    } = tol;
+
+    // Separated rounded cores only use the absolute tolerance.
+    let threshold = if separation_a < 0.0 {
+        relative_tol * separation_a
+    } else {
+        separation_a
+    };
+
+    let (poly1, xf1, edge1, poly2, xf2, flipped) = if separation_b > threshold + absolute_tol {
+        (poly_b, xf_b, edge_b, poly_a, xf_a, true)
+    } else {
+        (poly_a, xf_a, edge_a, poly_b, xf_b, false)
+    };
+
+    let mut incident_edge = [ClipVertex::default(); 2];
+    find_incident_edge(&mut incident_edge, poly1, xf1, edge1, poly2, xf2);
+
+    let count1 = poly1.count;
+    let i1 = edge1;
+    let i2 = (edge1 + 1) % count1;
+
+    let v11 = xf1.apply(poly1.vertices[i1]);
+    let v12 = xf1.apply(poly1.vertices[i2]);
+
+    let neg_edge = EdgeNumber::from_index((i1 + count1 - 1) % count1);
+    let pos_edge = EdgeNumber::from_index(i2);
+
+    // Rounded cores that do not overlap may only touch near a pair of
+    // vertices, where the face clip below would overestimate the overlap.
+    if radius > 0.0 && separation_a.max(separation_b) > 0.0 {
+        let v21 = incident_edge[0].v;
+        let v22 = incident_edge[1].v;
+        let (f1, f2, p1, p2) = segment_distance(v11, v12, v21, v22);
+
+        let at_vertex1 = f1 == 0.0 || f1 == 1.0;
+        let at_vertex2 = f2 == 0.0 || f2 == 1.0;
+        if at_vertex1 && at_vertex2 {
+            let d = p2 - p1;
+            let dist = d.length();
+            if dist > radius || dist < f32::EPSILON {
+                return 0;
+            }
+            let n = (1.0 / dist) * d;
+
+            // Feature ids name the two touching vertices by their edges.
+            let (in_edge1, out_edge1) = if f1 == 0.0 {
+                (neg_edge, EdgeNumber::from_index(i1))
+            } else {
+                (EdgeNumber::from_index(i1), pos_edge)
+            };
+            let vertex2 = if f2 == 0.0 {
+                incident_edge[0].fp
+            } else {
+                incident_edge[1].fp
+            };
+            let fp = FeaturePair::new(in_edge1, out_edge1, vertex2.in_edge2, vertex2.out_edge2);
+
+            let contact = &mut contacts[0];
+            contact.separation = dist - radius;
+            contact.normal = if flipped { -n } else { n };
+            contact.position = p1 + poly1.radius * n;
+            contact.feature = fp;
+            if flipped {
+                flip(&mut contact.feature);
+            }
+            return 1;
+        }
+    }
+
+    let tangent = (v12 - v11).normalize();
+    let front_normal = Vec2::cross_vec_scalar(tangent, 1.0);
+
+    let front = front_normal.dot(v11);
+    let neg_side = -tangent.dot(v11);
+    let pos_side = tangent.dot(v12);
+
+    let mut clip_points1 = [ClipVertex::default(); 2];
+    let mut clip_points2 = [ClipVertex::default(); 2];
+
+    // Clip to the side plane through v11.
+    let mut np = clip_segment_to_line(
+        &mut clip_points1,
+        &incident_edge,
+        -tangent,
+        neg_side,
+        neg_edge,
+    );
+
+    if np < 2 {
+        return 0;
+    }
+
+    // Clip to the side plane through v12.
+    np = clip_segment_to_line(
+        &mut clip_points2,
+        &clip_points1,
+        tangent,
+        pos_side,
+        pos_edge,
+    );
+
+    if np < 2 {
+        return 0;
+    }
+
+    let normal = if flipped { -front_normal } else { front_normal };
+
+    let mut num_contacts: usize = 0;
+    for cp in &clip_points2 {
+        let core_sep = front_normal.dot(cp.v) - front;
+        let sep = core_sep - radius;
+
+        if sep <= 0.0 {
+            let contact = &mut contacts[num_contacts];
+            contact.separation = sep;
+            contact.normal = normal;
+            // slide contact point onto reference surface (easy to cull)
+            contact.position = cp.v - (core_sep - poly1.radius) * front_normal;
+            contact.feature = cp.fp;
+            if flipped {
+                flip(&mut contact.feature);
+            }
+            num_contacts += 1;
+        }
+    }
+
+    num_contacts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn hexagon(r: f32) -> Polygon {
+        let pts: Vec<Vec2> = (0..6)
+            .map(|k| {
+                let a = k as f32 * core::f32::consts::PI / 3.0;
+                Vec2::new(r * a.cos(), r * a.sin())
+            })
+            .collect();
+        Polygon::new(&pts)
+    }
+
+    #[test]
+    fn separated_polygons_do_not_collide() {
+        let tol = FaceTolerance::default();
+        let mut contacts = [Contact::default(); 2];
+        let hex = hexagon(1.0);
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let xf_b = Transform::new(Vec2::new(2.5, 0.0), 0.3);
+        assert_eq!(
+            collide_polygons(&mut contacts, &hex, &xf_a, &hex, &xf_b, tol),
+            0
+        );
+    }
+
+    #[test]
+    fn hexagon_resting_on_box_gets_two_contacts() {
+        let tol = FaceTolerance::default();
+        let mut contacts = [Contact::default(); 2];
+        let ground = Polygon::make_box(Vec2::new(5.0, 0.5));
+        // Vertices at multiples of 60 degrees leave a flat side down.
+        let hex = hexagon(1.0);
+        let apothem = (3.0f32).sqrt() * 0.5;
+        let xf_a = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+        let xf_b = Transform::new(Vec2::new(0.0, 0.5 + apothem - 0.05), 0.0);
+
+        let n = collide_polygons(&mut contacts, &ground, &xf_a, &hex, &xf_b, tol);
+        assert_eq!(n, 2);
+        for c in &contacts[..n] {
+            assert_relative_eq!(c.normal.y, 1.0, epsilon = 1e-5);
+            assert_relative_eq!(c.separation, -0.05, epsilon = 1e-4);
+        }
+        assert_ne!(contacts[0].feature.key(), contacts[1].feature.key());
+    }
+
+    #[test]
+    fn normal_points_from_a_to_b() {
+        let tol = FaceTolerance::default();
+        let mut contacts = [Contact::default(); 2];
+        let small = Polygon::make_box(Vec2::new(0.25, 0.25));
+        let big = Polygon::make_box(Vec2::new(5.0, 0.5));
+        // Small box above, big box below.
+        let xf_a = Transform::new(Vec2::new(0.0, 0.7), 0.0);
+        let xf_b = Transform::new(Vec2::new(0.0, 0.0), 0.0);
+
+        let n = collide_polygons(&mut contacts, &small, &xf_a, &big, &xf_b, tol);
+        assert_eq!(n, 2);
+        for c in &contacts[..n] {
+            assert_relative_eq!(c.normal.y, -1.0, epsilon = 1e-5);
+        }
+    }
+}
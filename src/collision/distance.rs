@@ -0,0 +1,498 @@
+use crate::collision::{MAX_POLYGON_VERTICES, Polygon, Shape};
+use crate::math::{Transform, Vec2};
+
+const MAX_GJK_ITERATIONS: usize = 20;
+
+/// Convex core of a shape (a point, segment or polygon) plus a rounding
+/// radius, with vertices in the body frame.
+#[derive(Copy, Clone, Debug)]
+pub struct ConvexProxy {
+    pub vertices: [Vec2; MAX_POLYGON_VERTICES],
+    pub count: usize,
+    pub radius: f32,
+}
+
+impl ConvexProxy {
+    /// Proxy for `shape` placed in the body frame by `xf`, or `None` for
+    /// chains, which are only convex edge by edge.
+    pub fn new(shape: &Shape, xf: &Transform) -> Option<Self> {
+        match shape {
+            Shape::Chain(_) => None,
+            _ => Some(Self::from_child(shape, 0, xf)),
+        }
+    }
+
+    /// Proxy for collision child `index` of `shape`: the edge of a chain, or
+    /// the shape itself.
+    pub fn from_child(shape: &Shape, index: usize, xf: &Transform) -> Self {
+        debug_assert!(index < shape.child_count());
+        let boxed;
+        let edge;
+        let (points, radius): (&[Vec2], f32) = match shape {
+            Shape::Box { width } => {
+                boxed = Polygon::make_box(0.5 * *width);
+                (boxed.vertices(), 0.0)
+            }
+            Shape::Circle { radius } => (&[Vec2::new(0.0, 0.0)], *radius),
+            Shape::Polygon(poly) => (poly.vertices(), poly.radius),
+            Shape::Capsule {
+                center1,
+                center2,
+                radius,
+            } => (&[*center1, *center2], *radius),
+            Shape::Segment(seg) => (&[seg.vertex1, seg.vertex2], 0.0),
+            Shape::Chain(chain) => {
+                let seg = chain.child_edge(index);
+                edge = [seg.vertex1, seg.vertex2];
+                (&edge, 0.0)
+            }
+        };
+
+        let mut vertices = [Vec2::default(); MAX_POLYGON_VERTICES];
+        for (v, p) in vertices.iter_mut().zip(points) {
+            *v = xf.apply(*p);
+        }
+        Self {
+            vertices,
+            count: points.len(),
+            radius,
+        }
+    }
+
+    #[inline]
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices[..self.count]
+    }
+
+    /// Distance from the body origin to the farthest point of the shape.
+    pub fn extent(&self) -> f32 {
+        let r = self
+            .vertices()
+            .iter()
+            .map(|v| v.length())
+            .fold(0.0, f32::max);
+        r + self.radius
+    }
+
+    /// Index of the vertex farthest along `d`.
+    pub fn support(&self, d: Vec2) -> usize {
+        let mut best = 0;
+        for k in 1..self.count {
+            if self.vertices[k].dot(d) > self.vertices[best].dot(d) {
+                best = k;
+            }
+        }
+        best
+    }
+}
+
+/// Simplex from a previous call, to warm start the next one when the
+/// shapes have barely moved. Start from `SimplexCache::default()`.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct SimplexCache {
+    pub count: usize,
+    pub index_a: [usize; 3],
+    pub index_b: [usize; 3],
+    /// Length or area of the cached simplex, to detect a stale cache.
+    pub metric: f32,
+}
+
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct DistanceOutput {
+    /// Closest point on each shape's surface. Both are the same point when
+    /// the shapes overlap.
+    pub point_a: Vec2,
+    pub point_b: Vec2,
+    pub distance: f32,
+    pub iterations: usize,
+}
+
+/// Vertex of the Minkowski difference `b - a`.
+#[derive(Copy, Clone, Debug, Default)]
+struct SimplexVertex {
+    wa: Vec2,
+    wb: Vec2,
+    w: Vec2,
+    /// Barycentric weight of the closest point.
+    a: f32,
+    index_a: usize,
+    index_b: usize,
+}
+
+impl SimplexVertex {
+    fn new(
+        a: &ConvexProxy,
+        xf_a: &Transform,
+        index_a: usize,
+        b: &ConvexProxy,
+        xf_b: &Transform,
+        index_b: usize,
+    ) -> Self {
+        let wa = xf_a.apply(a.vertices[index_a]);
+        let wb = xf_b.apply(b.vertices[index_b]);
+        Self {
+            wa,
+            wb,
+            w: wb - wa,
+            a: 1.0,
+            index_a,
+            index_b,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, Default)]
+struct Simplex {
+    v: [SimplexVertex; 3],
+    count: usize,
+}
+
+impl Simplex {
+    fn from_cache(
+        cache: &SimplexCache,
+        a: &ConvexProxy,
+        xf_a: &Transform,
+        b: &ConvexProxy,
+        xf_b: &Transform,
+    ) -> Self {
+        let mut simplex = Simplex {
+            count: cache.count,
+            ..Default::default()
+        };
+        for k in 0..cache.count {
+            simplex.v[k] = SimplexVertex::new(a, xf_a, cache.index_a[k], b, xf_b, cache.index_b[k]);
+        }
+
+        // Flush the cache if the simplex has changed shape a lot.
+        if simplex.count > 1 {
+            let (metric1, metric2) = (cache.metric, simplex.metric());
+            if 2.0 * metric1 < metric2 || metric1 > 2.0 * metric2 || metric2 < f32::EPSILON {
+                simplex.count = 0;
+            }
+        }
+        if simplex.count == 0 {
+            simplex.v[0] = SimplexVertex::new(a, xf_a, 0, b, xf_b, 0);
+            simplex.count = 1;
+        }
+        simplex
+    }
+
+    fn write_cache(&self, cache: &mut SimplexCache) {
+        cache.metric = self.metric();
+        cache.count = self.count;
+        for k in 0..self.count {
+            cache.index_a[k] = self.v[k].index_a;
+            cache.index_b[k] = self.v[k].index_b;
+        }
+    }
+
+    fn metric(&self) -> f32 {
+        let v = &self.v;
+        match self.count {
+            2 => (v[0].w - v[1].w).length(),
+            3 => (v[1].w - v[0].w).cross(v[2].w - v[0].w),
+            _ => 0.0,
+        }
+    }
+
+    /// Direction from the simplex towards the origin.
+    fn search_direction(&self) -> Vec2 {
+        let v = &self.v;
+        match self.count {
+            1 => -v[0].w,
+            _ => {
+                let e12 = v[1].w - v[0].w;
+                if e12.cross(-v[0].w) > 0.0 {
+                    // Origin is left of e12.
+                    Vec2::cross_scalar_vec(1.0, e12)
+                } else {
+                    Vec2::cross_vec_scalar(e12, 1.0)
+                }
+            }
+        }
+    }
+
+    fn witness_points(&self) -> (Vec2, Vec2) {
+        let v = &self.v;
+        match self.count {
+            1 => (v[0].wa, v[0].wb),
+            2 => (
+                v[0].a * v[0].wa + v[1].a * v[1].wa,
+                v[0].a * v[0].wb + v[1].a * v[1].wb,
+            ),
+            _ => {
+                let p = v[0].a * v[0].wa + v[1].a * v[1].wa + v[2].a * v[2].wa;
+                (p, p)
+            }
+        }
+    }
+
+    /// Reduce a segment to the feature closest to the origin.
+    fn solve2(&mut self) {
+        let (w1, w2) = (self.v[0].w, self.v[1].w);
+        let e12 = w2 - w1;
+
+        // Vertex regions.
+        let d12_2 = -w1.dot(e12);
+        if d12_2 <= 0.0 {
+            self.v[0].a = 1.0;
+            self.count = 1;
+            return;
+        }
+        let d12_1 = w2.dot(e12);
+        if d12_1 <= 0.0 {
+            self.v[1].a = 1.0;
+            self.v[0] = self.v[1];
+            self.count = 1;
+            return;
+        }
+
+        let inv = 1.0 / (d12_1 + d12_2);
+        self.v[0].a = d12_1 * inv;
+        self.v[1].a = d12_2 * inv;
+        self.count = 2;
+    }
+
+    /// Reduce a triangle to the feature closest to the origin, using
+    /// barycentric coordinates of the edges and of the triangle.
+    fn solve3(&mut self) {
+        let (w1, w2, w3) = (self.v[0].w, self.v[1].w, self.v[2].w);
+
+        let e12 = w2 - w1;
+        let d12_1 = w2.dot(e12);
+        let d12_2 = -w1.dot(e12);
+
+        let e13 = w3 - w1;
+        let d13_1 = w3.dot(e13);
+        let d13_2 = -w1.dot(e13);
+
+        let e23 = w3 - w2;
+        let d23_1 = w3.dot(e23);
+        let d23_2 = -w2.dot(e23);
+
+        let n123 = e12.cross(e13);
+        let d123_1 = n123 * w2.cross(w3);
+        let d123_2 = n123 * w3.cross(w1);
+        let d123_3 = n123 * w1.cross(w2);
+
+        if d12_2 <= 0.0 && d13_2 <= 0.0 {
+            self.v[0].a = 1.0;
+            self.count = 1;
+        } else if d12_1 > 0.0 && d12_2 > 0.0 && d123_3 <= 0.0 {
+            let inv = 1.0 / (d12_1 + d12_2);
+            self.v[0].a = d12_1 * inv;
+            self.v[1].a = d12_2 * inv;
+            self.count = 2;
+        } else if d13_1 > 0.0 && d13_2 > 0.0 && d123_2 <= 0.0 {
+            let inv = 1.0 / (d13_1 + d13_2);
+            self.v[0].a = d13_1 * inv;
+            self.v[2].a = d13_2 * inv;
+            self.v[1] = self.v[2];
+            self.count = 2;
+        } else if d12_1 <= 0.0 && d23_2 <= 0.0 {
+            self.v[1].a = 1.0;
+            self.v[0] = self.v[1];
+            self.count = 1;
+        } else if d13_1 <= 0.0 && d23_1 <= 0.0 {
+            self.v[2].a = 1.0;
+            self.v[0] = self.v[2];
+            self.count = 1;
+        } else if d23_1 > 0.0 && d23_2 > 0.0 && d123_1 <= 0.0 {
+            let inv = 1.0 / (d23_1 + d23_2);
+            self.v[1].a = d23_1 * inv;
+            self.v[2].a = d23_2 * inv;
+            self.v[0] = self.v[2];
+            self.count = 2;
+        } else {
+            // Origin inside the triangle.
+            let inv = 1.0 / (d123_1 + d123_2 + d123_3);
+            self.v[0].a = d123_1 * inv;
+            self.v[1].a = d123_2 * inv;
+            self.v[2].a = d123_3 * inv;
+            self.count = 3;
+        }
+    }
+}
+
+/// Distance and closest points between two posed proxies, by GJK on their
+/// cores followed by the radii. `cache` warm starts the search and is
+/// updated for the next call.
+pub fn distance(
+    a: &ConvexProxy,
+    xf_a: &Transform,
+    b: &ConvexProxy,
+    xf_b: &Transform,
+    cache: &mut SimplexCache,
+) -> DistanceOutput {
+    let mut simplex = Simplex::from_cache(cache, a, xf_a, b, xf_b);
+
+    let mut iterations = 0;
+    while iterations < MAX_GJK_ITERATIONS {
+        // Support points of the simplex before it is reduced.
+        let mut saved = [(0, 0); 3];
+        let saved_count = simplex.count;
+        for (s, v) in saved.iter_mut().zip(&simplex.v[..saved_count]) {
+            *s = (v.index_a, v.index_b);
+        }
+
+        match simplex.count {
+            2 => simplex.solve2(),
+            3 => simplex.solve3(),
+            _ => {}
+        }
+        // The origin is inside the triangle: the cores overlap.
+        if simplex.count == 3 {
+            break;
+        }
+
+        let d = simplex.search_direction();
+        if d.length_squared() < f32::EPSILON * f32::EPSILON {
+            // The origin is on the simplex; call it overlap.
+            break;
+        }
+
+        let index_a = a.support(xf_a.rotation.transpose() * -d);
+        let index_b = b.support(xf_b.rotation.transpose() * d);
+        iterations += 1;
+
+        // No progress once a support point repeats.
+        if saved[..saved_count].contains(&(index_a, index_b)) {
+            break;
+        }
+        simplex.v[simplex.count] = SimplexVertex::new(a, xf_a, index_a, b, xf_b, index_b);
+        simplex.count += 1;
+    }
+
+    let (mut point_a, mut point_b) = simplex.witness_points();
+    let mut distance = (point_b - point_a).length();
+    simplex.write_cache(cache);
+
+    let radii = a.radius + b.radius;
+    if distance > radii && distance > f32::EPSILON {
+        // Move the witness points out to the surfaces.
+        let n = (point_b - point_a).normalize();
+        distance -= radii;
+        point_a += a.radius * n;
+        point_b -= b.radius * n;
+    } else {
+        let p = 0.5 * (point_a + point_b);
+        point_a = p;
+        point_b = p;
+        distance = 0.0;
+    }
+
+    DistanceOutput {
+        point_a,
+        point_b,
+        distance,
+        iterations,
+    }
+}
+
+/// [`distance`] between two shapes without a cache. Chains are measured
+/// edge by edge, keeping the closest.
+pub fn shape_distance(a: &Shape, xf_a: &Transform, b: &Shape, xf_b: &Transform) -> DistanceOutput {
+    let identity = Transform::default();
+    let mut closest = DistanceOutput {
+        distance: f32::MAX,
+        ..Default::default()
+    };
+    for child_a in 0..a.child_count() {
+        let proxy_a = ConvexProxy::from_child(a, child_a, &identity);
+        for child_b in 0..b.child_count() {
+            let proxy_b = ConvexProxy::from_child(b, child_b, &identity);
+            let out = distance(&proxy_a, xf_a, &proxy_b, xf_b, &mut SimplexCache::default());
+            if out.distance < closest.distance {
+                closest = out;
+            }
+        }
+    }
+    closest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn unit_box() -> Shape {
+        Shape::Box {
+            width: Vec2::new(1.0, 1.0),
+        }
+    }
+
+    #[test]
+    fn distance_between_boxes_and_circle() {
+        let out = shape_distance(
+            &unit_box(),
+            &Transform::default(),
+            &Shape::Circle { radius: 0.5 },
+            &Transform::new(Vec2::new(3.0, 0.25), 0.0),
+        );
+        assert_relative_eq!(out.distance, 2.0, epsilon = 1e-5);
+        assert_relative_eq!(out.point_a.x, 0.5, epsilon = 1e-5);
+        assert_relative_eq!(out.point_b.x, 2.5, epsilon = 1e-5);
+        assert_relative_eq!(out.point_b.y, 0.25, epsilon = 1e-5);
+
+        // Crossed boxes share no vertex inside the other but still overlap.
+        let thin = Shape::Box {
+            width: Vec2::new(4.0, 0.2),
+        };
+        let out = shape_distance(
+            &thin,
+            &Transform::default(),
+            &thin,
+            &Transform::new(Vec2::new(0.0, 0.0), core::f32::consts::FRAC_PI_2),
+        );
+        assert_eq!(out.distance, 0.0);
+    }
+
+    #[test]
+    fn rotated_boxes_corner_to_face() {
+        // A diamond whose corner points at the unit box's right face.
+        let out = shape_distance(
+            &unit_box(),
+            &Transform::default(),
+            &unit_box(),
+            &Transform::new(Vec2::new(2.0, 0.1), core::f32::consts::FRAC_PI_4),
+        );
+        let corner = 2.0 - 0.5 * 2.0_f32.sqrt();
+        assert_relative_eq!(out.distance, corner - 0.5, epsilon = 1e-5);
+        assert_relative_eq!(out.point_a.x, 0.5, epsilon = 1e-5);
+        assert_relative_eq!(out.point_a.y, 0.1, epsilon = 1e-5);
+        assert_relative_eq!(out.point_b.x, corner, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn cache_warm_starts_the_next_query() {
+        let a = ConvexProxy::new(&unit_box(), &Transform::default()).unwrap();
+        let b = ConvexProxy::new(
+            &Shape::Capsule {
+                center1: Vec2::new(-0.5, 0.0),
+                center2: Vec2::new(0.5, 0.0),
+                radius: 0.25,
+            },
+            &Transform::default(),
+        )
+        .unwrap();
+        let xf_b = Transform::new(Vec2::new(0.3, 2.0), 0.3);
+
+        let mut cache = SimplexCache::default();
+        let cold = distance(&a, &Transform::default(), &b, &xf_b, &mut cache);
+        assert!(cache.count > 0);
+
+        let nudged = Transform::new(Vec2::new(0.31, 2.0), 0.3);
+        let warm = distance(&a, &Transform::default(), &b, &nudged, &mut cache);
+        assert!(warm.iterations <= cold.iterations);
+        assert_relative_eq!(warm.distance, cold.distance, epsilon = 0.02);
+
+        let fresh = distance(
+            &a,
+            &Transform::default(),
+            &b,
+            &nudged,
+            &mut SimplexCache::default(),
+        );
+        assert_relative_eq!(warm.distance, fresh.distance, epsilon = 1e-5);
+    }
+}
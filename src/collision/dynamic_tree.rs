@@ -0,0 +1,535 @@
+use crate::collision::{Aabb, RayCastInput};
+use crate::math::Vec2;
+
+/// Index of a proxy (a leaf) in a [`DynamicTree`].
+pub type ProxyId = usize;
+
+const NULL_NODE: usize = usize::MAX;
+
+#[derive(Clone, Debug)]
+struct TreeNode<T> {
+    /// Fat AABB for leaves, union of the children otherwise.
+    aabb: Aabb,
+    user_data: Option<T>,
+    /// Parent, or next free node while on the free list.
+    parent: usize,
+    child1: usize,
+    child2: usize,
+    /// Leaves are 0, free nodes -1.
+    height: i32,
+    /// Leaf was re-inserted since the last `clear_moved`.
+    moved: bool,
+}
+
+impl<T> TreeNode<T> {
+    #[inline]
+    fn is_leaf(&self) -> bool {
+        self.child1 == NULL_NODE
+    }
+}
+
+/// Dynamic AABB tree: a bounding volume hierarchy whose leaves are proxies
+/// with fattened AABBs. Insertion picks the sibling by perimeter cost and
+/// rotations keep the tree balanced.
+#[derive(Clone, Debug)]
+pub struct DynamicTree<T> {
+    nodes: Vec<TreeNode<T>>,
+    root: usize,
+    free_list: usize,
+    proxy_count: usize,
+    /// Added around proxy AABBs so small motions don't touch the tree.
+    margin: f32,
+}
+
+impl<T: Copy> DynamicTree<T> {
+    pub fn new(margin: f32) -> Self {
+        debug_assert!(margin >= 0.0 && margin.is_finite());
+
+        Self {
+            nodes: Vec::new(),
+            root: NULL_NODE,
+            free_list: NULL_NODE,
+            proxy_count: 0,
+            margin,
+        }
+    }
+
+    /// Insert a proxy for `aabb`. The stored AABB is fattened by the margin.
+    pub fn create_proxy(&mut self, aabb: Aabb, user_data: T) -> ProxyId {
+        let id = self.allocate_node();
+        let node = &mut self.nodes[id];
+        node.aabb = aabb.extended(self.margin);
+        node.user_data = Some(user_data);
+        node.height = 0;
+        node.moved = true;
+
+        self.insert_leaf(id);
+        self.proxy_count += 1;
+        id
+    }
+
+    pub fn destroy_proxy(&mut self, id: ProxyId) {
+        debug_assert!(self.nodes[id].is_leaf(), "not a proxy");
+
+        self.remove_leaf(id);
+        self.free_node(id);
+        self.proxy_count -= 1;
+    }
+
+    /// Refit a proxy to `aabb`. Returns `true` if it left its fat AABB and was
+    /// re-inserted, in which case it is flagged as moved.
+    pub fn move_proxy(&mut self, id: ProxyId, aabb: Aabb) -> bool {
+        debug_assert!(self.nodes[id].is_leaf(), "not a proxy");
+
+        let tree_aabb = self.nodes[id].aabb;
+        if tree_aabb.contains(&aabb) {
+            // Still inside, unless the fat AABB has grown far too large
+            // (e.g. the object was moving fast and has since stopped).
+            let huge = aabb.extended(4.0 * self.margin);
+            if huge.contains(&tree_aabb) {
+                return false;
+            }
+        }
+
+        self.remove_leaf(id);
+        self.nodes[id].aabb = aabb.extended(self.margin);
+        self.insert_leaf(id);
+        self.nodes[id].moved = true;
+        true
+    }
+
+    #[inline]
+    pub fn user_data(&self, id: ProxyId) -> T {
+        self.nodes[id]
+            .user_data
+            .expect("proxy id does not name a live proxy")
+    }
+
+    #[inline]
+    pub fn fat_aabb(&self, id: ProxyId) -> Aabb {
+        self.nodes[id].aabb
+    }
+
+    #[inline]
+    pub fn was_moved(&self, id: ProxyId) -> bool {
+        self.nodes[id].moved
+    }
+
+    #[inline]
+    pub fn clear_moved(&mut self, id: ProxyId) {
+        self.nodes[id].moved = false;
+    }
+
+    #[inline]
+    pub fn proxy_count(&self) -> usize {
+        self.proxy_count
+    }
+
+    #[inline]
+    pub fn margin(&self) -> f32 {
+        self.margin
+    }
+
+    /// Height of the tree; zero when empty or a single leaf.
+    #[inline]
+    pub fn height(&self) -> i32 {
+        if self.root == NULL_NODE {
+            0
+        } else {
+            self.nodes[self.root].height
+        }
+    }
+
+    /// Call `callback` with every proxy whose fat AABB overlaps `aabb`.
+    /// Return `false` from the callback to stop the query.
+    pub fn query<F: FnMut(ProxyId) -> bool>(&self, aabb: &Aabb, mut callback: F) {
+        let mut stack = Vec::with_capacity(64);
+        if self.root != NULL_NODE {
+            stack.push(self.root);
+        }
+
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if !node.aabb.overlaps(aabb) {
+                continue;
+            }
+
+            if node.is_leaf() {
+                if !callback(id) {
+                    return;
+                }
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+    }
+
+    /// Call `callback` with every proxy whose fat AABB the ray crosses. The
+    /// callback returns the new clip fraction: 0 stops the cast, a positive
+    /// value clips the ray and a negative one leaves it as it is.
+    pub fn ray_cast<F: FnMut(&RayCastInput, ProxyId) -> f32>(
+        &self,
+        input: &RayCastInput,
+        mut callback: F,
+    ) {
+        let (p1, p2) = (input.p1, input.p2);
+        let r = (p2 - p1).normalize();
+        // Separating axis for the segment: its normal.
+        let v = Vec2::cross_scalar_vec(1.0, r);
+        let abs_v = v.abs();
+
+        let mut sub_input = *input;
+        let mut segment_aabb = sub_input.aabb();
+
+        let mut stack = Vec::with_capacity(64);
+        if self.root != NULL_NODE {
+            stack.push(self.root);
+        }
+
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if !node.aabb.overlaps(&segment_aabb) {
+                continue;
+            }
+
+            let c = node.aabb.center();
+            let h = 0.5 * node.aabb.size();
+            if v.dot(p1 - c).abs() - abs_v.dot(h) > 0.0 {
+                continue;
+            }
+
+            if node.is_leaf() {
+                let value = callback(&sub_input, id);
+                if value == 0.0 {
+                    return;
+                }
+                if value > 0.0 {
+                    sub_input.max_fraction = value;
+                    segment_aabb = sub_input.aabb();
+                }
+            } else {
+                stack.push(node.child1);
+                stack.push(node.child2);
+            }
+        }
+    }
+
+    fn allocate_node(&mut self) -> usize {
+        let node = TreeNode {
+            aabb: Aabb::default(),
+            user_data: None,
+            parent: NULL_NODE,
+            child1: NULL_NODE,
+            child2: NULL_NODE,
+            height: 0,
+            moved: false,
+        };
+
+        if self.free_list == NULL_NODE {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        } else {
+            let id = self.free_list;
+            self.free_list = self.nodes[id].parent;
+            self.nodes[id] = node;
+            id
+        }
+    }
+
+    fn free_node(&mut self, id: usize) {
+        let node = &mut self.nodes[id];
+        node.user_data = None;
+        node.parent = self.free_list;
+        node.height = -1;
+        self.free_list = id;
+    }
+
+    fn insert_leaf(&mut self, leaf: usize) {
+        if self.root == NULL_NODE {
+            self.root = leaf;
+            self.nodes[leaf].parent = NULL_NODE;
+            return;
+        }
+
+        // Find the best sibling by perimeter cost.
+        let leaf_aabb = self.nodes[leaf].aabb;
+        let mut index = self.root;
+        while !self.nodes[index].is_leaf() {
+            let node = &self.nodes[index];
+            let (child1, child2) = (node.child1, node.child2);
+
+            let area = node.aabb.perimeter();
+            let combined_area = node.aabb.union(&leaf_aabb).perimeter();
+
+            // Cost of creating a new parent for this node and the new leaf.
+            let cost = 2.0 * combined_area;
+
+            // Minimum cost of pushing the leaf further down the tree.
+            let inheritance_cost = 2.0 * (combined_area - area);
+
+            let descend_cost = |child: usize| {
+                let child = &self.nodes[child];
+                let enlarged = leaf_aabb.union(&child.aabb).perimeter();
+                if child.is_leaf() {
+                    enlarged + inheritance_cost
+                } else {
+                    enlarged - child.aabb.perimeter() + inheritance_cost
+                }
+            };
+            let cost1 = descend_cost(child1);
+            let cost2 = descend_cost(child2);
+
+            if cost < cost1 && cost < cost2 {
+                break;
+            }
+
+            index = if cost1 < cost2 { child1 } else { child2 };
+        }
+        let sibling = index;
+
+        // Create a new parent for the sibling and the leaf.
+        let old_parent = self.nodes[sibling].parent;
+        let new_parent = self.allocate_node();
+        self.nodes[new_parent].parent = old_parent;
+        self.nodes[new_parent].aabb = leaf_aabb.union(&self.nodes[sibling].aabb);
+        self.nodes[new_parent].height = self.nodes[sibling].height + 1;
+        self.nodes[new_parent].child1 = sibling;
+        self.nodes[new_parent].child2 = leaf;
+        self.nodes[sibling].parent = new_parent;
+        self.nodes[leaf].parent = new_parent;
+
+        if old_parent == NULL_NODE {
+            self.root = new_parent;
+        } else if self.nodes[old_parent].child1 == sibling {
+            self.nodes[old_parent].child1 = new_parent;
+        } else {
+            self.nodes[old_parent].child2 = new_parent;
+        }
+
+        // Walk back up fixing heights and AABBs.
+        self.refit(self.nodes[leaf].parent);
+    }
+
+    fn remove_leaf(&mut self, leaf: usize) {
+        if leaf == self.root {
+            self.root = NULL_NODE;
+            return;
+        }
+
+        let parent = self.nodes[leaf].parent;
+        let grand_parent = self.nodes[parent].parent;
+        let sibling = if self.nodes[parent].child1 == leaf {
+            self.nodes[parent].child2
+        } else {
+            self.nodes[parent].child1
+        };
+
+        // Replace the parent by the sibling.
+        self.nodes[sibling].parent = grand_parent;
+        self.free_node(parent);
+
+        if grand_parent == NULL_NODE {
+            self.root = sibling;
+        } else {
+            if self.nodes[grand_parent].child1 == parent {
+                self.nodes[grand_parent].child1 = sibling;
+            } else {
+                self.nodes[grand_parent].child2 = sibling;
+            }
+            self.refit(grand_parent);
+        }
+    }
+
+    /// Rebalance and recompute heights and AABBs from `index` up to the root.
+    fn refit(&mut self, mut index: usize) {
+        while index != NULL_NODE {
+            index = self.balance(index);
+
+            let (child1, child2) = (self.nodes[index].child1, self.nodes[index].child2);
+            let (c1, c2) = (&self.nodes[child1], &self.nodes[child2]);
+            let height = 1 + c1.height.max(c2.height);
+            let aabb = c1.aabb.union(&c2.aabb);
+
+            let node = &mut self.nodes[index];
+            node.height = height;
+            node.aabb = aabb;
+            index = node.parent;
+        }
+    }
+
+    /// Perform a left or right rotation if node `ia` is imbalanced.
+    /// Returns the new root of the subtree.
+    fn balance(&mut self, ia: usize) -> usize {
+        let a = &self.nodes[ia];
+        if a.is_leaf() || a.height < 2 {
+            return ia;
+        }
+
+        let ib = a.child1;
+        let ic = a.child2;
+        let balance = self.nodes[ic].height - self.nodes[ib].height;
+
+        if balance > 1 {
+            // Rotate C up.
+            let ifx = self.nodes[ic].child1;
+            let ig = self.nodes[ic].child2;
+            self.replace_child_of_parent(ia, ic);
+
+            self.nodes[ic].child1 = ia;
+            self.nodes[ia].parent = ic;
+
+            let (keep, give) = if self.nodes[ifx].height > self.nodes[ig].height {
+                (ifx, ig)
+            } else {
+                (ig, ifx)
+            };
+            self.nodes[ic].child2 = keep;
+            self.nodes[ia].child2 = give;
+            self.nodes[give].parent = ia;
+
+            self.fix_node(ia);
+            self.fix_node(ic);
+            return ic;
+        }
+
+        if balance < -1 {
+            // Rotate B up.
+            let id = self.nodes[ib].child1;
+            let ie = self.nodes[ib].child2;
+            self.replace_child_of_parent(ia, ib);
+
+            self.nodes[ib].child1 = ia;
+            self.nodes[ia].parent = ib;
+
+            let (keep, give) = if self.nodes[id].height > self.nodes[ie].height {
+                (id, ie)
+            } else {
+                (ie, id)
+            };
+            self.nodes[ib].child2 = keep;
+            self.nodes[ia].child1 = give;
+            self.nodes[give].parent = ia;
+
+            self.fix_node(ia);
+            self.fix_node(ib);
+            return ib;
+        }
+
+        ia
+    }
+
+    /// Make `new` take the place of `old` under `old`'s parent (or as root).
+    fn replace_child_of_parent(&mut self, old: usize, new: usize) {
+        let parent = self.nodes[old].parent;
+        self.nodes[new].parent = parent;
+        if parent == NULL_NODE {
+            self.root = new;
+        } else if self.nodes[parent].child1 == old {
+            self.nodes[parent].child1 = new;
+        } else {
+            self.nodes[parent].child2 = new;
+        }
+    }
+
+    /// Recompute an internal node from its children.
+    fn fix_node(&mut self, index: usize) {
+        let (child1, child2) = (self.nodes[index].child1, self.nodes[index].child2);
+        let (c1, c2) = (&self.nodes[child1], &self.nodes[child2]);
+        let height = 1 + c1.height.max(c2.height);
+        let aabb = c1.aabb.union(&c2.aabb);
+        self.nodes[index].height = height;
+        self.nodes[index].aabb = aabb;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MARGIN: f32 = 0.1;
+
+    fn unit_box(x: f32, y: f32) -> Aabb {
+        Aabb::new(Vec2::new(x, y), Vec2::new(x + 1.0, y + 1.0))
+    }
+
+    fn brute_force(boxes: &[(ProxyId, Aabb)], query: &Aabb) -> Vec<usize> {
+        let mut hits: Vec<usize> = boxes
+            .iter()
+            .filter(|(_, b)| b.extended(MARGIN).overlaps(query))
+            .map(|(id, _)| *id)
+            .collect();
+        hits.sort();
+        hits
+    }
+
+    fn validate<T: Copy>(tree: &DynamicTree<T>, index: usize) -> i32 {
+        let node = &tree.nodes[index];
+        if node.is_leaf() {
+            assert_eq!(node.height, 0);
+            return 0;
+        }
+        let (c1, c2) = (node.child1, node.child2);
+        assert_eq!(tree.nodes[c1].parent, index);
+        assert_eq!(tree.nodes[c2].parent, index);
+        assert!(node.aabb.contains(&tree.nodes[c1].aabb));
+        assert!(node.aabb.contains(&tree.nodes[c2].aabb));
+        let (h1, h2) = (validate(tree, c1), validate(tree, c2));
+        assert!((h1 - h2).abs() <= 1, "unbalanced");
+        assert_eq!(node.height, 1 + h1.max(h2));
+        node.height
+    }
+
+    #[test]
+    fn query_matches_brute_force_and_stays_balanced() {
+        let mut tree = DynamicTree::new(MARGIN);
+        let mut boxes = Vec::new();
+        // A sorted row is the worst case for an unbalanced tree.
+        for k in 0..64 {
+            let aabb = unit_box(1.5 * k as f32, 0.0);
+            boxes.push((tree.create_proxy(aabb, k), aabb));
+        }
+        validate(&tree, tree.root);
+        assert!(tree.height() <= 8, "height {}", tree.height());
+
+        let query = Aabb::new(Vec2::new(10.0, 0.5), Vec2::new(20.0, 0.6));
+        let mut hits = Vec::new();
+        tree.query(&query, |id| {
+            hits.push(id);
+            true
+        });
+        hits.sort();
+        assert_eq!(hits, brute_force(&boxes, &query));
+    }
+
+    #[test]
+    fn move_and_destroy_keep_the_tree_consistent() {
+        let mut tree = DynamicTree::new(MARGIN);
+        let ids: Vec<ProxyId> = (0..16)
+            .map(|k| tree.create_proxy(unit_box(k as f32 * 2.0, 0.0), k))
+            .collect();
+
+        // Small motion stays within the fat AABB.
+        assert!(!tree.move_proxy(ids[3], unit_box(6.05, 0.0)));
+        assert!(tree.move_proxy(ids[3], unit_box(6.0, 5.0)));
+        assert!(tree.was_moved(ids[3]));
+
+        for &id in &ids[..8] {
+            tree.destroy_proxy(id);
+        }
+        assert_eq!(tree.proxy_count(), 8);
+        validate(&tree, tree.root);
+
+        // Freed nodes are reused.
+        let id = tree.create_proxy(unit_box(0.0, 0.0), 99);
+        assert_eq!(tree.user_data(id), 99);
+        assert!(tree.nodes.len() < 2 * 17);
+
+        let mut hits = Vec::new();
+        tree.query(&unit_box(0.0, 0.0), |id| {
+            hits.push(tree.user_data(id));
+            true
+        });
+        assert_eq!(hits, vec![99]);
+    }
+}
@@ -1,5 +1,27 @@
+pub mod aabb;
 pub mod arbiter;
+pub mod broad_phase;
 pub mod collide;
+pub mod collide_capsule;
+pub mod collide_circle;
+pub mod collide_edge;
+pub mod collide_polygon;
+pub mod distance;
+pub mod dynamic_tree;
+pub mod ray_cast;
+pub mod shape;
+pub mod sweep_and_prune;
+pub mod time_of_impact;
 
+pub use aabb::Aabb;
 pub use arbiter::{Arbiter, ArbiterKey, FeaturePair};
-pub use collide::{Axis, EdgeNumber, collide};
+pub use broad_phase::{
+    BroadPhase, BroadPhaseKind, BruteForceBroadPhase, FixtureProxy, TreeBroadPhase,
+};
+pub use collide::{Axis, EdgeNumber, FaceTolerance, collide, collide_fixtures, collide_shapes};
+pub use distance::{ConvexProxy, DistanceOutput, SimplexCache, distance, shape_distance};
+pub use dynamic_tree::{DynamicTree, ProxyId};
+pub use ray_cast::{RayCastInput, RayCastOutput};
+pub use shape::{Chain, MAX_POLYGON_VERTICES, MassData, Polygon, Segment, Shape};
+pub use sweep_and_prune::SweepAndPrune;
+pub use time_of_impact::{Sweep, ToiOutput, ToiState, time_of_impact};
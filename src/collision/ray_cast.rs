@@ -0,0 +1,294 @@
+use crate::collision::{Aabb, Polygon, Segment, Shape};
+use crate::math::{Transform, Vec2};
+
+/// Ray from `p1` towards `p2`, clipped at `p1 + max_fraction * (p2 - p1)`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayCastInput {
+    pub p1: Vec2,
+    pub p2: Vec2,
+    pub max_fraction: f32,
+}
+
+impl RayCastInput {
+    /// Bounding box of the clipped ray.
+    pub fn aabb(&self) -> Aabb {
+        let end = self.p1 + self.max_fraction * (self.p2 - self.p1);
+        Aabb::from_points(&[self.p1, end])
+    }
+}
+
+/// Where a ray enters a shape: the unit surface normal there and the
+/// fraction along `p2 - p1`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayCastOutput {
+    pub normal: Vec2,
+    pub fraction: f32,
+}
+
+impl Shape {
+    /// Cast a ray against the shape posed by `xf`. Rays starting inside a
+    /// solid shape report no hit.
+    pub fn ray_cast(&self, input: &RayCastInput, xf: &Transform) -> Option<RayCastOutput> {
+        // Work in the shape frame.
+        let p1 = xf.apply_inverse(input.p1);
+        let d = xf.rotation.transpose() * (input.p2 - input.p1);
+        let max_fraction = input.max_fraction;
+
+        let hit = match *self {
+            Shape::Box { width } => {
+                ray_cast_polygon(&Polygon::make_box(0.5 * width), p1, d, max_fraction)
+            }
+            Shape::Circle { radius } => {
+                ray_cast_circle(Vec2::new(0.0, 0.0), radius, p1, d, max_fraction)
+            }
+            Shape::Polygon(ref poly) => ray_cast_polygon(poly, p1, d, max_fraction),
+            Shape::Capsule {
+                center1,
+                center2,
+                radius,
+            } => ray_cast_polygon(
+                &Polygon::make_capsule(center1, center2, radius),
+                p1,
+                d,
+                max_fraction,
+            ),
+            Shape::Segment(ref seg) => ray_cast_segment(seg, p1, d, max_fraction),
+            Shape::Chain(ref chain) => (0..chain.edge_count())
+                .filter_map(|k| ray_cast_segment(&chain.child_edge(k), p1, d, max_fraction))
+                .min_by(|a, b| a.fraction.total_cmp(&b.fraction)),
+        }?;
+
+        Some(RayCastOutput {
+            normal: xf.rotation * hit.normal,
+            fraction: hit.fraction,
+        })
+    }
+}
+
+fn ray_cast_circle(
+    center: Vec2,
+    radius: f32,
+    p1: Vec2,
+    d: Vec2,
+    max_fraction: f32,
+) -> Option<RayCastOutput> {
+    // Solve |p1 + t d - center| = radius for the smaller root.
+    let s = p1 - center;
+    let b = s.dot(s) - radius * radius;
+    let c = s.dot(d);
+    let rr = d.dot(d);
+    let sigma = c * c - rr * b;
+    if sigma < 0.0 || rr < f32::EPSILON {
+        return None;
+    }
+
+    let a = -(c + sigma.sqrt());
+    if a < 0.0 || a > max_fraction * rr {
+        return None;
+    }
+    let fraction = a / rr;
+    Some(RayCastOutput {
+        normal: (s + fraction * d).normalize(),
+        fraction,
+    })
+}
+
+fn ray_cast_polygon(poly: &Polygon, p1: Vec2, d: Vec2, max_fraction: f32) -> Option<RayCastOutput> {
+    if poly.radius > 0.0 {
+        return ray_cast_rounded_polygon(poly, p1, d, max_fraction);
+    }
+
+    // Clip the ray against each edge's half plane.
+    let (mut lower, mut upper) = (0.0, max_fraction);
+    let mut index = None;
+    for k in 0..poly.count {
+        let numerator = poly.normals[k].dot(poly.vertices[k] - p1);
+        let denominator = poly.normals[k].dot(d);
+
+        if denominator == 0.0 {
+            if numerator < 0.0 {
+                return None;
+            }
+        } else if denominator < 0.0 && numerator < lower * denominator {
+            // Entering this half plane.
+            lower = numerator / denominator;
+            index = Some(k);
+        } else if denominator > 0.0 && numerator < upper * denominator {
+            upper = numerator / denominator;
+        }
+
+        if upper < lower {
+            return None;
+        }
+    }
+
+    // No entering edge: the ray starts inside.
+    index.map(|k| RayCastOutput {
+        normal: poly.normals[k],
+        fraction: lower,
+    })
+}
+
+/// Rounded polygons (capsules) are their edges pushed out by the radius plus
+/// a circle at each vertex.
+fn ray_cast_rounded_polygon(
+    poly: &Polygon,
+    p1: Vec2,
+    d: Vec2,
+    max_fraction: f32,
+) -> Option<RayCastOutput> {
+    let mut best: Option<RayCastOutput> = None;
+    let mut keep = |hit: Option<RayCastOutput>| {
+        if let Some(hit) = hit
+            && best.is_none_or(|b| hit.fraction < b.fraction)
+        {
+            best = Some(hit);
+        }
+    };
+
+    for k in 0..poly.count {
+        let n = poly.normals[k];
+        let v1 = poly.vertices[k] + poly.radius * n;
+        let v2 = poly.vertices[(k + 1) % poly.count] + poly.radius * n;
+        keep(ray_cast_face(v1, v2, n, p1, d, max_fraction));
+        keep(ray_cast_circle(
+            poly.vertices[k],
+            poly.radius,
+            p1,
+            d,
+            max_fraction,
+        ));
+    }
+    best
+}
+
+/// Hit on the front of the face `v1`-`v2` with outward normal `n`.
+fn ray_cast_face(
+    v1: Vec2,
+    v2: Vec2,
+    n: Vec2,
+    p1: Vec2,
+    d: Vec2,
+    max_fraction: f32,
+) -> Option<RayCastOutput> {
+    let denominator = n.dot(d);
+    if denominator >= 0.0 {
+        return None;
+    }
+    let fraction = n.dot(v1 - p1) / denominator;
+    if fraction < 0.0 || fraction > max_fraction {
+        return None;
+    }
+
+    let e = v2 - v1;
+    let s = (p1 + fraction * d - v1).dot(e) / e.dot(e);
+    if !(0.0..=1.0).contains(&s) {
+        return None;
+    }
+    Some(RayCastOutput {
+        normal: n,
+        fraction,
+    })
+}
+
+/// Two-sided segments are hit from either side, one-sided ones only from the
+/// right.
+fn ray_cast_segment(seg: &Segment, p1: Vec2, d: Vec2, max_fraction: f32) -> Option<RayCastOutput> {
+    let e = seg.vertex2 - seg.vertex1;
+    let right = Vec2::cross_vec_scalar(e, 1.0).normalize();
+
+    let numerator = right.dot(seg.vertex1 - p1);
+    if seg.one_sided && numerator > 0.0 {
+        return None;
+    }
+    let n = if numerator > 0.0 { -right } else { right };
+    ray_cast_face(seg.vertex1, seg.vertex2, n, p1, d, max_fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    fn ray(p1: Vec2, p2: Vec2) -> RayCastInput {
+        RayCastInput {
+            p1,
+            p2,
+            max_fraction: 1.0,
+        }
+    }
+
+    #[test]
+    fn rotated_box_reports_entry_face() {
+        let shape = Shape::Box {
+            width: Vec2::new(2.0, 2.0),
+        };
+        let xf = Transform::new(Vec2::new(5.0, 0.0), core::f32::consts::FRAC_PI_4);
+
+        let hit = shape
+            .ray_cast(&ray(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)), &xf)
+            .unwrap();
+        // The corner of the diamond sits at x = 5 - sqrt(2).
+        assert_relative_eq!(hit.fraction, (5.0 - 2.0_f32.sqrt()) / 10.0, epsilon = 1e-5);
+        assert!(hit.normal.x < 0.0);
+        assert_relative_eq!(hit.normal.length(), 1.0, epsilon = 1e-5);
+
+        // Too short, starting inside, and pointing away all miss.
+        let short = RayCastInput {
+            max_fraction: 0.3,
+            ..ray(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0))
+        };
+        assert!(shape.ray_cast(&short, &xf).is_none());
+        assert!(
+            shape
+                .ray_cast(&ray(Vec2::new(5.0, 0.0), Vec2::new(10.0, 0.0)), &xf)
+                .is_none()
+        );
+        assert!(
+            shape
+                .ray_cast(&ray(Vec2::new(0.0, 0.0), Vec2::new(-10.0, 0.0)), &xf)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn circle_capsule_and_segment() {
+        let down = ray(Vec2::new(0.0, 4.0), Vec2::new(0.0, -4.0));
+        let xf = Transform::default();
+
+        let hit = Shape::Circle { radius: 1.0 }.ray_cast(&down, &xf).unwrap();
+        assert_relative_eq!(hit.fraction, 3.0 / 8.0, epsilon = 1e-5);
+        assert_relative_eq!(hit.normal.y, 1.0, epsilon = 1e-5);
+
+        let capsule = Shape::Capsule {
+            center1: Vec2::new(-1.0, 0.0),
+            center2: Vec2::new(1.0, 0.0),
+            radius: 0.5,
+        };
+        let hit = capsule.ray_cast(&down, &xf).unwrap();
+        assert_relative_eq!(hit.fraction, 3.5 / 8.0, epsilon = 1e-5);
+        assert_relative_eq!(hit.normal.y, 1.0, epsilon = 1e-5);
+        let side = capsule
+            .ray_cast(&ray(Vec2::new(4.0, 0.0), Vec2::new(0.0, 0.0)), &xf)
+            .unwrap();
+        assert_relative_eq!(side.fraction, 2.5 / 4.0, epsilon = 1e-5);
+        assert_relative_eq!(side.normal.x, 1.0, epsilon = 1e-5);
+
+        // One-sided segments are solid only from the right.
+        let left_to_right = Segment::one_sided(
+            Vec2::new(-3.0, 0.0),
+            Vec2::new(-2.0, 0.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(3.0, 0.0),
+        );
+        let up = ray(Vec2::new(0.0, -4.0), Vec2::new(0.0, 4.0));
+        let hit = Shape::Segment(left_to_right).ray_cast(&up, &xf).unwrap();
+        assert_relative_eq!(hit.fraction, 0.5, epsilon = 1e-5);
+        assert_relative_eq!(hit.normal.y, -1.0, epsilon = 1e-5);
+        assert!(Shape::Segment(left_to_right).ray_cast(&down, &xf).is_none());
+
+        let two_sided = Segment::two_sided(Vec2::new(-2.0, 0.0), Vec2::new(2.0, 0.0));
+        let hit = Shape::Segment(two_sided).ray_cast(&down, &xf).unwrap();
+        assert_relative_eq!(hit.normal.y, 1.0, epsilon = 1e-5);
+    }
+}
@@ -0,0 +1,642 @@
+use crate::collision::Aabb;
+use crate::math::{K_PI, Transform, Vec2};
+
+/// Maximum number of vertices on a convex polygon.
+///
+/// Polygons and [`ConvexProxy`](crate::collision::ConvexProxy) store this
+/// many vertices inline, so it is fixed at compile time: 8 by default, or 16
+/// or 32 with the `max-polygon-vertices-16` or `max-polygon-vertices-32`
+/// feature.
+pub const MAX_POLYGON_VERTICES: usize = if cfg!(feature = "max-polygon-vertices-32") {
+    32
+} else if cfg!(feature = "max-polygon-vertices-16") {
+    16
+} else {
+    8
+};
+
+/// Collision geometry, expressed in the local frame of its fixture.
+// Polygons are kept inline even when `MAX_POLYGON_VERTICES` is raised.
+#[allow(clippy::large_enum_variant)]
+#[derive(Clone, Debug, PartialEq)]
+pub enum Shape {
+    /// Box of size `width`, centered on the local origin.
+    Box { width: Vec2 },
+    /// Circle of `radius`, centered on the local origin.
+    Circle { radius: f32 },
+    /// Convex polygon with counter-clockwise winding.
+    Polygon(Polygon),
+    /// Segment from `center1` to `center2` swept by `radius`.
+    Capsule {
+        center1: Vec2,
+        center2: Vec2,
+        radius: f32,
+    },
+    /// Line segment for static terrain.
+    Segment(Segment),
+    /// Polyline of one-sided segments for static terrain.
+    Chain(Chain),
+}
+
+/// Line segment from `vertex1` to `vertex2`.
+///
+/// One-sided segments only collide on their right side (the outside of a
+/// counter-clockwise loop) and use the ghost vertices `vertex0` and `vertex3`
+/// to smooth contacts across neighbouring segments.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Segment {
+    pub vertex0: Vec2,
+    pub vertex1: Vec2,
+    pub vertex2: Vec2,
+    pub vertex3: Vec2,
+    pub one_sided: bool,
+}
+
+impl Segment {
+    pub fn two_sided(v1: Vec2, v2: Vec2) -> Self {
+        Self {
+            vertex0: v1,
+            vertex1: v1,
+            vertex2: v2,
+            vertex3: v2,
+            one_sided: false,
+        }
+    }
+
+    pub fn one_sided(v0: Vec2, v1: Vec2, v2: Vec2, v3: Vec2) -> Self {
+        Self {
+            vertex0: v0,
+            vertex1: v1,
+            vertex2: v2,
+            vertex3: v3,
+            one_sided: true,
+        }
+    }
+}
+
+/// Chain of one-sided segments. Each segment is a separate collision child,
+/// with its neighbours as ghost vertices so bodies slide smoothly across the
+/// internal vertices.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chain {
+    pub vertices: Vec<Vec2>,
+    /// Ghost vertex before the first vertex of an open chain.
+    pub prev_vertex: Vec2,
+    /// Ghost vertex after the last vertex of an open chain.
+    pub next_vertex: Vec2,
+    pub is_loop: bool,
+}
+
+impl Chain {
+    /// Closed loop; counter-clockwise loops collide on the outside.
+    pub fn new_loop(vertices: Vec<Vec2>) -> Self {
+        assert!(vertices.len() >= 3, "chain loop needs at least 3 vertices");
+        Self {
+            prev_vertex: vertices[vertices.len() - 1],
+            next_vertex: vertices[0],
+            vertices,
+            is_loop: true,
+        }
+    }
+
+    /// Open chain with explicit ghost vertices at both ends.
+    pub fn new_open(vertices: Vec<Vec2>, prev_vertex: Vec2, next_vertex: Vec2) -> Self {
+        assert!(vertices.len() >= 2, "chain needs at least 2 vertices");
+        Self {
+            vertices,
+            prev_vertex,
+            next_vertex,
+            is_loop: false,
+        }
+    }
+
+    pub fn edge_count(&self) -> usize {
+        if self.is_loop {
+            self.vertices.len()
+        } else {
+            self.vertices.len() - 1
+        }
+    }
+
+    /// Edge `index` as a one-sided segment with ghost vertices.
+    pub fn child_edge(&self, index: usize) -> Segment {
+        let n = self.vertices.len();
+        debug_assert!(index < self.edge_count());
+
+        let v1 = self.vertices[index];
+        let v2 = self.vertices[(index + 1) % n];
+
+        let v0 = if index > 0 {
+            self.vertices[index - 1]
+        } else if self.is_loop {
+            self.vertices[n - 1]
+        } else {
+            self.prev_vertex
+        };
+
+        let v3 = if index + 2 < n {
+            self.vertices[index + 2]
+        } else if self.is_loop {
+            self.vertices[(index + 2) % n]
+        } else {
+            self.next_vertex
+        };
+
+        Segment::one_sided(v0, v1, v2, v3)
+    }
+}
+
+/// Convex polygon with up to `MAX_POLYGON_VERTICES` vertices.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Polygon {
+    pub vertices: [Vec2; MAX_POLYGON_VERTICES],
+    /// Outward unit normal of edge `k` (vertex `k` to vertex `k + 1`).
+    pub normals: [Vec2; MAX_POLYGON_VERTICES],
+    pub count: usize,
+    /// Rounding radius around the core polygon. Zero for plain polygons; the
+    /// narrow phase uses it to treat capsules as rounded two-vertex polygons.
+    pub radius: f32,
+}
+
+impl Polygon {
+    /// Build the convex hull of `points` (gift wrapping).
+    ///
+    /// Panics if there are more than `MAX_POLYGON_VERTICES` points or the hull
+    /// is degenerate (fewer than three non-collinear points).
+    pub fn new(points: &[Vec2]) -> Self {
+        assert!(
+            points.len() >= 3 && points.len() <= MAX_POLYGON_VERTICES,
+            "polygon needs 3..={MAX_POLYGON_VERTICES} points"
+        );
+
+        // Weld points that are too close together.
+        let weld_sqr = 0.5 * 0.005 * 0.5 * 0.005;
+        let mut ps = [Vec2::default(); MAX_POLYGON_VERTICES];
+        let mut n = 0;
+        for &p in points {
+            if ps[..n].iter().all(|q| (p - *q).length_squared() > weld_sqr) {
+                ps[n] = p;
+                n += 1;
+            }
+        }
+        assert!(n >= 3, "polygon is degenerate");
+
+        // Start from the rightmost point (lowest y on ties), which is on the hull.
+        let mut i0 = 0;
+        for i in 1..n {
+            if ps[i].x > ps[i0].x || (ps[i].x == ps[i0].x && ps[i].y < ps[i0].y) {
+                i0 = i;
+            }
+        }
+
+        let mut hull = [0usize; MAX_POLYGON_VERTICES];
+        let mut m = 0;
+        let mut ih = i0;
+        loop {
+            assert!(m < MAX_POLYGON_VERTICES, "polygon hull did not close");
+            hull[m] = ih;
+
+            let mut ie = 0;
+            for j in 1..n {
+                if ie == ih {
+                    ie = j;
+                    continue;
+                }
+                let r = ps[ie] - ps[hull[m]];
+                let v = ps[j] - ps[hull[m]];
+                let c = r.cross(v);
+                if c < 0.0 {
+                    ie = j;
+                }
+                // Collinear: keep the farthest point.
+                if c == 0.0 && v.length_squared() > r.length_squared() {
+                    ie = j;
+                }
+            }
+
+            m += 1;
+            ih = ie;
+            if ie == i0 {
+                break;
+            }
+        }
+        assert!(m >= 3, "polygon is degenerate");
+
+        let mut poly = Polygon {
+            vertices: [Vec2::default(); MAX_POLYGON_VERTICES],
+            normals: [Vec2::default(); MAX_POLYGON_VERTICES],
+            count: m,
+            radius: 0.0,
+        };
+        for k in 0..m {
+            poly.vertices[k] = ps[hull[k]];
+        }
+        poly.compute_normals();
+        poly
+    }
+
+    /// Box with half extents `h`, numbered like the Box2D-Lite box.
+    pub fn make_box(h: Vec2) -> Self {
+        let mut poly = Polygon {
+            vertices: [Vec2::default(); MAX_POLYGON_VERTICES],
+            normals: [Vec2::default(); MAX_POLYGON_VERTICES],
+            count: 4,
+            radius: 0.0,
+        };
+        poly.vertices[0] = Vec2::new(h.x, h.y);
+        poly.vertices[1] = Vec2::new(-h.x, h.y);
+        poly.vertices[2] = Vec2::new(-h.x, -h.y);
+        poly.vertices[3] = Vec2::new(h.x, -h.y);
+        poly.normals[0] = Vec2::new(0.0, 1.0);
+        poly.normals[1] = Vec2::new(-1.0, 0.0);
+        poly.normals[2] = Vec2::new(0.0, -1.0);
+        poly.normals[3] = Vec2::new(1.0, 0.0);
+        poly
+    }
+
+    /// Capsule as a rounded two-vertex polygon.
+    pub fn make_capsule(p1: Vec2, p2: Vec2, radius: f32) -> Self {
+        let mut poly = Polygon {
+            vertices: [Vec2::default(); MAX_POLYGON_VERTICES],
+            normals: [Vec2::default(); MAX_POLYGON_VERTICES],
+            count: 2,
+            radius,
+        };
+        poly.vertices[0] = p1;
+        poly.vertices[1] = p2;
+        poly.compute_normals();
+        poly
+    }
+
+    #[inline]
+    pub fn vertices(&self) -> &[Vec2] {
+        &self.vertices[..self.count]
+    }
+
+    /// Same polygon shifted by `offset`.
+    pub fn translated(&self, offset: Vec2) -> Self {
+        let mut poly = *self;
+        for v in &mut poly.vertices[..poly.count] {
+            *v += offset;
+        }
+        poly
+    }
+
+    fn compute_normals(&mut self) {
+        for k in 0..self.count {
+            let edge = self.vertices[(k + 1) % self.count] - self.vertices[k];
+            debug_assert!(edge.length_squared() > f32::EPSILON * f32::EPSILON);
+            self.normals[k] = Vec2::cross_vec_scalar(edge, 1.0).normalize();
+        }
+    }
+
+    fn compute_mass(&self, density: f32) -> MassData {
+        // Triangle fan about the first vertex keeps round-off small.
+        let s = self.vertices[0];
+        let mut area = 0.0;
+        let mut center = Vec2::new(0.0, 0.0);
+        let mut i = 0.0;
+
+        for k in 1..self.count - 1 {
+            let e1 = self.vertices[k] - s;
+            let e2 = self.vertices[k + 1] - s;
+            let d = e1.cross(e2);
+
+            let tri_area = 0.5 * d;
+            area += tri_area;
+            center += (tri_area / 3.0) * (e1 + e2);
+
+            let int_x2 = e1.x * e1.x + e2.x * e1.x + e2.x * e2.x;
+            let int_y2 = e1.y * e1.y + e2.y * e1.y + e2.y * e2.y;
+            i += (0.25 / 3.0 * d) * (int_x2 + int_y2);
+        }
+
+        debug_assert!(area > f32::EPSILON);
+        center = (1.0 / area) * center;
+        let mass = density * area;
+
+        MassData {
+            mass,
+            center: s + center,
+            // Shift from the fan origin to the centroid.
+            i: density * i - mass * center.dot(center),
+        }
+    }
+}
+
+/// Mass properties of a shape for a given density.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct MassData {
+    pub mass: f32,
+    /// Centroid in the shape's local frame.
+    pub center: Vec2,
+    /// Rotational inertia about the centroid.
+    pub i: f32,
+}
+
+impl Shape {
+    pub fn compute_mass(&self, density: f32) -> MassData {
+        match *self {
+            Shape::Box { width } => {
+                let mass = density * width.x * width.y;
+                MassData {
+                    mass,
+                    center: Vec2::new(0.0, 0.0),
+                    i: mass * (width.x * width.x + width.y * width.y) / 12.0,
+                }
+            }
+            Shape::Circle { radius } => {
+                let mass = density * K_PI * radius * radius;
+                MassData {
+                    mass,
+                    center: Vec2::new(0.0, 0.0),
+                    i: 0.5 * mass * radius * radius,
+                }
+            }
+            Shape::Polygon(ref poly) => poly.compute_mass(density),
+            Shape::Capsule {
+                center1,
+                center2,
+                radius,
+            } => {
+                // Rectangle between the centers plus two half disks.
+                let length = (center2 - center1).length();
+                let rr = radius * radius;
+                let box_mass = density * (2.0 * radius * length);
+                let circle_mass = density * K_PI * rr;
+
+                // Half disk centroid offset from its flat side.
+                let lc = 4.0 * radius / (3.0 * K_PI);
+                let h = 0.5 * length;
+
+                let circle_i = circle_mass * (0.5 * rr + h * h + 2.0 * h * lc);
+                let box_i = box_mass * (4.0 * rr + length * length) / 12.0;
+
+                MassData {
+                    mass: box_mass + circle_mass,
+                    center: 0.5 * (center1 + center2),
+                    i: circle_i + box_i,
+                }
+            }
+            // Terrain has no area; only static bodies may use it.
+            Shape::Segment(_) | Shape::Chain(_) => MassData::default(),
+        }
+    }
+
+    /// Same shape shifted by `offset` in its local frame.
+    ///
+    /// Only polygons and capsules carry an explicit offset; boxes and circles
+    /// stay centered.
+    pub fn translated(&self, offset: Vec2) -> Self {
+        match *self {
+            Shape::Polygon(ref poly) => Shape::Polygon(poly.translated(offset)),
+            Shape::Capsule {
+                center1,
+                center2,
+                radius,
+            } => Shape::Capsule {
+                center1: center1 + offset,
+                center2: center2 + offset,
+                radius,
+            },
+            _ => self.clone(),
+        }
+    }
+
+    /// Size of the shape's bounding box in its local frame.
+    pub fn local_size(&self) -> Vec2 {
+        self.compute_aabb(&Transform::default()).size()
+    }
+
+    /// Radius the shape is rounded by: that of circles, capsules and rounded
+    /// polygons, zero otherwise.
+    pub fn radius(&self) -> f32 {
+        match *self {
+            Shape::Circle { radius } | Shape::Capsule { radius, .. } => radius,
+            Shape::Polygon(ref poly) => poly.radius,
+            _ => 0.0,
+        }
+    }
+
+    /// World bounding box of the shape posed by `xf`.
+    pub fn compute_aabb(&self, xf: &Transform) -> Aabb {
+        let rounded = |points: &[Vec2], radius: f32| {
+            let first = xf.apply(points[0]);
+            points[1..]
+                .iter()
+                .fold(Aabb::new(first, first), |aabb, v| {
+                    let p = xf.apply(*v);
+                    aabb.union(&Aabb::new(p, p))
+                })
+                .extended(radius)
+        };
+        match *self {
+            Shape::Box { width } => rounded(Polygon::make_box(0.5 * width).vertices(), 0.0),
+            Shape::Circle { radius } => rounded(&[Vec2::new(0.0, 0.0)], radius),
+            Shape::Polygon(ref poly) => rounded(poly.vertices(), poly.radius),
+            Shape::Capsule {
+                center1,
+                center2,
+                radius,
+            } => rounded(&[center1, center2], radius),
+            Shape::Segment(ref seg) => rounded(&[seg.vertex1, seg.vertex2], 0.0),
+            Shape::Chain(ref chain) => rounded(&chain.vertices, 0.0),
+        }
+    }
+
+    /// World bounding box of collision child `index`, without building the
+    /// child.
+    pub fn compute_child_aabb(&self, index: usize, xf: &Transform) -> Aabb {
+        match *self {
+            Shape::Chain(ref chain) => {
+                let edge = chain.child_edge(index);
+                let (p1, p2) = (xf.apply(edge.vertex1), xf.apply(edge.vertex2));
+                Aabb::new(p1, p1).union(&Aabb::new(p2, p2))
+            }
+            _ => {
+                debug_assert!(index == 0);
+                self.compute_aabb(xf)
+            }
+        }
+    }
+
+    /// Number of collision children: one per edge for chains, one otherwise.
+    pub fn child_count(&self) -> usize {
+        match *self {
+            Shape::Chain(ref chain) => chain.edge_count(),
+            _ => 1,
+        }
+    }
+
+    /// Collision child `index`; chains yield their edges as segments.
+    pub fn child(&self, index: usize) -> Shape {
+        match *self {
+            Shape::Chain(ref chain) => Shape::Segment(chain.child_edge(index)),
+            _ => {
+                debug_assert!(index == 0);
+                self.clone()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::EdgeNumber;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn box_mass_matches_box2d_lite_formula() {
+        let w = Vec2::new(2.0, 4.0);
+        let md = Shape::Box { width: w }.compute_mass(1.5);
+        assert_relative_eq!(md.mass, 12.0, epsilon = 1e-6);
+        assert_relative_eq!(
+            md.i,
+            md.mass * (w.x * w.x + w.y * w.y) / 12.0,
+            epsilon = 1e-5
+        );
+    }
+
+    #[test]
+    fn circle_mass_is_solid_disk() {
+        let md = Shape::Circle { radius: 2.0 }.compute_mass(1.0);
+        assert_relative_eq!(md.mass, 4.0 * K_PI, epsilon = 1e-5);
+        assert_relative_eq!(md.i, 0.5 * md.mass * 4.0, epsilon = 1e-4);
+        assert_relative_eq!(md.center.x, 0.0);
+    }
+
+    #[test]
+    fn polygon_hull_is_ccw_and_drops_interior_points() {
+        let poly = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 0.0),
+            Vec2::new(0.0, 2.0),
+            Vec2::new(0.5, 0.5), // interior
+            Vec2::new(2.0, 2.0),
+        ]);
+        assert_eq!(poly.count, 4);
+        for k in 0..poly.count {
+            let e1 = poly.vertices[(k + 1) % poly.count] - poly.vertices[k];
+            let e2 = poly.vertices[(k + 2) % poly.count] - poly.vertices[(k + 1) % poly.count];
+            assert!(e1.cross(e2) > 0.0);
+            assert_relative_eq!(poly.normals[k].length(), 1.0, epsilon = 1e-6);
+        }
+    }
+
+    #[test]
+    fn polygon_takes_max_vertices() {
+        let points: Vec<Vec2> = (0..MAX_POLYGON_VERTICES)
+            .map(|k| {
+                let angle = 2.0 * K_PI * k as f32 / MAX_POLYGON_VERTICES as f32;
+                Vec2::new(angle.cos(), angle.sin())
+            })
+            .collect();
+        let poly = Polygon::new(&points);
+        assert_eq!(poly.count, MAX_POLYGON_VERTICES);
+        assert_eq!(
+            EdgeNumber::from_index(MAX_POLYGON_VERTICES - 1).0 as usize,
+            MAX_POLYGON_VERTICES
+        );
+    }
+
+    #[test]
+    fn polygon_box_mass_matches_box_shape() {
+        let w = Vec2::new(2.0, 4.0);
+        let boxed = Shape::Box { width: w }.compute_mass(2.0);
+        let poly = Shape::Polygon(Polygon::make_box(0.5 * w)).compute_mass(2.0);
+        assert_relative_eq!(poly.mass, boxed.mass, epsilon = 1e-5);
+        assert_relative_eq!(poly.i, boxed.i, epsilon = 1e-4);
+        assert_relative_eq!(poly.center.x, 0.0, epsilon = 1e-6);
+        assert_relative_eq!(poly.center.y, 0.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn polygon_triangle_centroid() {
+        let tri = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(0.0, 3.0),
+        ]);
+        let md = Shape::Polygon(tri).compute_mass(1.0);
+        assert_relative_eq!(md.mass, 4.5, epsilon = 1e-5);
+        assert_relative_eq!(md.center.x, 1.0, epsilon = 1e-5);
+        assert_relative_eq!(md.center.y, 1.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn capsule_mass_is_rectangle_plus_disk() {
+        let capsule = Shape::Capsule {
+            center1: Vec2::new(-1.0, 2.0),
+            center2: Vec2::new(1.0, 2.0),
+            radius: 0.5,
+        };
+        let md = capsule.compute_mass(1.0);
+        assert_relative_eq!(md.mass, 2.0 + K_PI * 0.25, epsilon = 1e-5);
+        assert_relative_eq!(md.center.x, 0.0, epsilon = 1e-6);
+        assert_relative_eq!(md.center.y, 2.0, epsilon = 1e-6);
+
+        // Bounded by the inertia of its bounding box and of the inner rectangle.
+        let rect = Shape::Box {
+            width: Vec2::new(2.0, 1.0),
+        }
+        .compute_mass(1.0);
+        let bounds = Shape::Box {
+            width: Vec2::new(3.0, 1.0),
+        }
+        .compute_mass(1.0);
+        assert!(md.i > rect.i && md.i < bounds.i);
+
+        let size = capsule.local_size();
+        assert_relative_eq!(size.x, 3.0, epsilon = 1e-6);
+        assert_relative_eq!(size.y, 1.0, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn chain_loop_child_edges_wrap_ghosts() {
+        let chain = Chain::new_loop(vec![
+            Vec2::new(0.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(1.0, 1.0),
+            Vec2::new(0.0, 1.0),
+        ]);
+        let shape = Shape::Chain(chain);
+        assert_eq!(shape.child_count(), 4);
+
+        let Shape::Segment(last) = shape.child(3) else {
+            panic!("chain child is not a segment");
+        };
+        assert!(last.one_sided);
+        assert_eq!(last.vertex0, Vec2::new(1.0, 1.0));
+        assert_eq!(last.vertex1, Vec2::new(0.0, 1.0));
+        assert_eq!(last.vertex2, Vec2::new(0.0, 0.0));
+        assert_eq!(last.vertex3, Vec2::new(1.0, 0.0));
+
+        let xf = Transform::new(Vec2::new(2.0, -1.0), 0.5);
+        for k in 0..shape.child_count() {
+            assert_eq!(
+                shape.compute_child_aabb(k, &xf),
+                shape.child(k).compute_aabb(&xf)
+            );
+        }
+    }
+
+    #[test]
+    fn open_chain_uses_explicit_ghosts() {
+        let chain = Chain::new_open(
+            vec![
+                Vec2::new(0.0, 0.0),
+                Vec2::new(1.0, 0.0),
+                Vec2::new(2.0, 0.0),
+            ],
+            Vec2::new(-1.0, 0.5),
+            Vec2::new(3.0, 0.5),
+        );
+        assert_eq!(chain.edge_count(), 2);
+        assert_eq!(chain.child_edge(0).vertex0, Vec2::new(-1.0, 0.5));
+        assert_eq!(chain.child_edge(0).vertex3, Vec2::new(2.0, 0.0));
+        assert_eq!(chain.child_edge(1).vertex3, Vec2::new(3.0, 0.5));
+        assert_eq!(Shape::Chain(chain).compute_mass(1.0).mass, 0.0);
+    }
+}
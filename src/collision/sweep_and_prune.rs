@@ -0,0 +1,255 @@
+use std::collections::BTreeMap;
+
+use crate::collision::{Aabb, BroadPhase, FixtureProxy, ProxyId};
+
+#[derive(Copy, Clone, Debug)]
+struct SapProxy {
+    /// Fat AABB, as in the dynamic tree.
+    aabb: Aabb,
+    user_data: FixtureProxy,
+}
+
+#[derive(Copy, Clone, Debug)]
+struct Endpoint {
+    value: f32,
+    proxy: ProxyId,
+    is_min: bool,
+}
+
+/// Incremental sort-and-sweep on the x axis. Endpoints stay sorted between
+/// steps, so re-sorting after small motions is close to linear, and the
+/// swaps themselves maintain the set of pairs overlapping on x.
+#[derive(Clone, Debug)]
+pub struct SweepAndPrune {
+    proxies: Vec<Option<SapProxy>>,
+    free_ids: Vec<ProxyId>,
+    endpoints: Vec<Endpoint>,
+    /// Pairs `(low id, high id)` overlapping on x, and whether they were
+    /// reported as fully overlapping.
+    x_pairs: BTreeMap<(ProxyId, ProxyId), bool>,
+    proxy_count: usize,
+    margin: f32,
+}
+
+impl SweepAndPrune {
+    /// Proxy AABBs are fattened by `margin`, as in the dynamic tree.
+    pub fn new(margin: f32) -> Self {
+        debug_assert!(margin >= 0.0 && margin.is_finite());
+
+        Self {
+            proxies: Vec::new(),
+            free_ids: Vec::new(),
+            endpoints: Vec::new(),
+            x_pairs: BTreeMap::new(),
+            proxy_count: 0,
+            margin,
+        }
+    }
+
+    #[inline]
+    fn proxy(&self, id: ProxyId) -> &SapProxy {
+        self.proxies[id].as_ref().expect("not a proxy")
+    }
+
+    /// Insertion sort on x. A min endpoint passing a max endpoint to the left
+    /// may begin an overlap; a max passing a min ends one.
+    fn sort_endpoints(&mut self) {
+        for e in &mut self.endpoints {
+            let aabb = self.proxies[e.proxy].as_ref().expect("stale endpoint").aabb;
+            e.value = if e.is_min { aabb.lower.x } else { aabb.upper.x };
+        }
+
+        for i in 1..self.endpoints.len() {
+            let key = self.endpoints[i];
+            let mut j = i;
+            while j > 0 && self.endpoints[j - 1].value > key.value {
+                let other = self.endpoints[j - 1];
+                if key.is_min && !other.is_min {
+                    self.begin_x_overlap(key.proxy, other.proxy);
+                } else if !key.is_min && other.is_min {
+                    self.end_x_overlap(key.proxy, other.proxy);
+                }
+                self.endpoints[j] = other;
+                j -= 1;
+            }
+            self.endpoints[j] = key;
+        }
+    }
+
+    fn begin_x_overlap(&mut self, a: ProxyId, b: ProxyId) {
+        let (aa, ab) = (self.proxy(a).aabb, self.proxy(b).aabb);
+        // Check the values: the other endpoint may not be in place yet.
+        if aa.lower.x <= ab.upper.x && ab.lower.x <= aa.upper.x {
+            self.x_pairs.entry((a.min(b), a.max(b))).or_insert(false);
+        }
+    }
+
+    fn end_x_overlap(&mut self, a: ProxyId, b: ProxyId) {
+        self.x_pairs.remove(&(a.min(b), a.max(b)));
+    }
+}
+
+impl BroadPhase for SweepAndPrune {
+    fn create_proxy(&mut self, aabb: Aabb, user_data: FixtureProxy) -> ProxyId {
+        let proxy = SapProxy {
+            aabb: aabb.extended(self.margin),
+            user_data,
+        };
+        let id = match self.free_ids.pop() {
+            Some(id) => {
+                self.proxies[id] = Some(proxy);
+                id
+            }
+            None => {
+                self.proxies.push(Some(proxy));
+                self.proxies.len() - 1
+            }
+        };
+
+        // Appended unsorted; the next sort sweeps them into place.
+        for is_min in [true, false] {
+            self.endpoints.push(Endpoint {
+                value: 0.0,
+                proxy: id,
+                is_min,
+            });
+        }
+        self.proxy_count += 1;
+        id
+    }
+
+    fn destroy_proxy(&mut self, id: ProxyId) {
+        debug_assert!(self.proxies[id].is_some(), "not a proxy");
+
+        self.endpoints.retain(|e| e.proxy != id);
+        self.x_pairs.retain(|&(a, b), _| a != id && b != id);
+        self.proxies[id] = None;
+        self.free_ids.push(id);
+        self.proxy_count -= 1;
+    }
+
+    /// Refit a proxy to its current AABB. Small motions inside the fat AABB
+    /// are ignored.
+    fn move_proxy(&mut self, id: ProxyId, aabb: Aabb) {
+        let proxy = self.proxies[id].as_mut().expect("not a proxy");
+        let huge = aabb.extended(4.0 * self.margin);
+        if proxy.aabb.contains(&aabb) && huge.contains(&proxy.aabb) {
+            return;
+        }
+        proxy.aabb = aabb.extended(self.margin);
+    }
+
+    #[inline]
+    fn fat_aabb(&self, id: ProxyId) -> Aabb {
+        self.proxy(id).aabb
+    }
+
+    #[inline]
+    fn proxy_count(&self) -> usize {
+        self.proxy_count
+    }
+
+    /// Re-sort the endpoints and report each pair that started overlapping
+    /// since the last call.
+    fn update_pairs(&mut self, callback: &mut dyn FnMut(FixtureProxy, FixtureProxy)) {
+        self.sort_endpoints();
+
+        for (&(a, b), reported) in self.x_pairs.iter_mut() {
+            let pa = self.proxies[a].as_ref().expect("stale pair");
+            let pb = self.proxies[b].as_ref().expect("stale pair");
+            let overlap = pa.aabb.overlaps(&pb.aabb);
+            if overlap && !*reported {
+                callback(pa.user_data, pb.user_data);
+            }
+            *reported = overlap;
+        }
+    }
+
+    fn clear(&mut self) {
+        *self = Self::new(self.margin);
+    }
+
+    /// Endpoints are only re-sorted by `update_pairs`, so this checks every
+    /// proxy.
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(FixtureProxy) -> bool) {
+        for proxy in self.proxies.iter().flatten() {
+            if proxy.aabb.overlaps(aabb) && !callback(proxy.user_data) {
+                return;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamics::{BodyHandle, SlotKey};
+    use crate::math::Vec2;
+
+    fn proxy(body: u32) -> FixtureProxy {
+        FixtureProxy {
+            body: BodyHandle::from_parts(body, 0),
+            fixture: 0,
+            child: 0,
+        }
+    }
+
+    fn unit_box(x: f32, y: f32) -> Aabb {
+        Aabb::new(Vec2::new(x, y), Vec2::new(x + 1.0, y + 1.0))
+    }
+
+    fn new_pairs(sap: &mut SweepAndPrune) -> Vec<(usize, usize)> {
+        let mut pairs = Vec::new();
+        sap.update_pairs(&mut |p, q| {
+            pairs.push((
+                p.body.index().min(q.body.index()),
+                p.body.index().max(q.body.index()),
+            ))
+        });
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn reports_overlaps_as_they_begin() {
+        let mut sap = SweepAndPrune::new(0.1);
+        let a = sap.create_proxy(unit_box(0.0, 0.0), proxy(0));
+        sap.create_proxy(unit_box(0.5, 0.0), proxy(1));
+        sap.create_proxy(unit_box(5.0, 0.0), proxy(2));
+        // Overlaps body 1 on x only.
+        sap.create_proxy(unit_box(0.5, 3.0), proxy(3));
+
+        assert_eq!(new_pairs(&mut sap), vec![(0, 1)]);
+        assert!(new_pairs(&mut sap).is_empty());
+
+        // Slide body 0 right across body 1 onto body 2.
+        let mut seen = Vec::new();
+        for k in 1..=10 {
+            sap.move_proxy(a, unit_box(0.45 * k as f32, 0.0));
+            seen.extend(new_pairs(&mut sap));
+        }
+        assert_eq!(seen, vec![(0, 2)]);
+        assert!(!sap.test_overlap(0, 1));
+
+        // Body 1 rises into body 3 without any swap on x.
+        sap.move_proxy(1, unit_box(0.5, 2.5));
+        assert_eq!(new_pairs(&mut sap), vec![(1, 3)]);
+    }
+
+    #[test]
+    fn destroyed_proxies_drop_their_pairs() {
+        let mut sap = SweepAndPrune::new(0.1);
+        let a = sap.create_proxy(unit_box(0.0, 0.0), proxy(0));
+        sap.create_proxy(unit_box(0.5, 0.0), proxy(1));
+        assert_eq!(new_pairs(&mut sap).len(), 1);
+
+        sap.destroy_proxy(a);
+        assert_eq!(sap.proxy_count(), 1);
+        assert!(new_pairs(&mut sap).is_empty());
+
+        // The id is reused and pairs up again.
+        let b = sap.create_proxy(unit_box(0.2, 0.0), proxy(2));
+        assert_eq!(b, a);
+        assert_eq!(new_pairs(&mut sap), vec![(1, 2)]);
+    }
+}
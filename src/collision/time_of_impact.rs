@@ -0,0 +1,166 @@
+use crate::collision::{ConvexProxy, SimplexCache, distance};
+use crate::math::{Transform, Vec2};
+
+const MAX_TOI_ITERATIONS: usize = 30;
+
+/// Motion of a body frame over a step, interpolated linearly in position and
+/// angle. `t` runs from 0 (start) to 1 (end).
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct Sweep {
+    pub position0: Vec2,
+    pub angle0: f32,
+    pub position: Vec2,
+    pub angle: f32,
+}
+
+impl Sweep {
+    /// A body frame that does not move.
+    pub fn fixed(position: Vec2, angle: f32) -> Self {
+        Self {
+            position0: position,
+            angle0: angle,
+            position,
+            angle,
+        }
+    }
+
+    #[inline]
+    pub fn transform(&self, t: f32) -> Transform {
+        Transform::new(
+            self.position0 + t * (self.position - self.position0),
+            self.angle0 + t * (self.angle - self.angle0),
+        )
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum ToiState {
+    /// Already overlapping at the start of the sweep; left to the discrete
+    /// contacts.
+    Overlapped,
+    /// Came within the target separation at `t`.
+    Touching,
+    /// No impact before `t_max`.
+    Separated,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ToiOutput {
+    pub state: ToiState,
+    pub t: f32,
+    /// Closest point on `a` and the unit normal from `a` to `b` at `t`.
+    /// Only meaningful when touching.
+    pub point: Vec2,
+    pub normal: Vec2,
+}
+
+/// First time in `[0, t_max]` at which the proxies come within `target`,
+/// by conservative advancement: each iteration moves forward by the current
+/// distance over an upper bound on the approach speed, so the shapes can
+/// never pass through each other. Stopping `target` short of contact keeps
+/// the shapes from overlapping at the reported time.
+pub fn time_of_impact(
+    a: &ConvexProxy,
+    sweep_a: &Sweep,
+    b: &ConvexProxy,
+    sweep_b: &Sweep,
+    t_max: f32,
+    target: f32,
+) -> ToiOutput {
+    debug_assert!(target > 0.0);
+    let tolerance = 0.25 * target;
+
+    let output = |state, t, point, normal| ToiOutput {
+        state,
+        t,
+        point,
+        normal,
+    };
+
+    // Largest relative displacement of any surface point over the sweep.
+    let bound = ((sweep_b.position - sweep_b.position0) - (sweep_a.position - sweep_a.position0))
+        .length()
+        + (sweep_a.angle - sweep_a.angle0).abs() * a.extent()
+        + (sweep_b.angle - sweep_b.angle0).abs() * b.extent();
+
+    let mut cache = SimplexCache::default();
+    let mut t = 0.0;
+    for iteration in 0..MAX_TOI_ITERATIONS {
+        let (xf_a, xf_b) = (sweep_a.transform(t), sweep_b.transform(t));
+        let out = distance(a, &xf_a, b, &xf_b, &mut cache);
+        let (pa, pb) = (out.point_a, out.point_b);
+
+        if iteration == 0 && out.distance == 0.0 {
+            return output(ToiState::Overlapped, 0.0, pa, Vec2::default());
+        }
+        if out.distance < target + tolerance {
+            return output(ToiState::Touching, t, pa, (pb - pa).normalize());
+        }
+        if bound <= 0.0 {
+            break;
+        }
+
+        t += (out.distance - target) / bound;
+        if t >= t_max {
+            break;
+        }
+    }
+
+    output(ToiState::Separated, t_max, Vec2::default(), Vec2::default())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::Shape;
+    use crate::math::Transform;
+    use approx::assert_relative_eq;
+
+    const TARGET: f32 = 0.005;
+
+    #[test]
+    fn fast_box_hits_thin_wall_before_passing_through() {
+        let wall = ConvexProxy::new(
+            &Shape::Box {
+                width: Vec2::new(0.1, 4.0),
+            },
+            &Transform::default(),
+        )
+        .unwrap();
+        let bullet = ConvexProxy::new(
+            &Shape::Box {
+                width: Vec2::new(1.0, 1.0),
+            },
+            &Transform::default(),
+        )
+        .unwrap();
+
+        // Starts left of the wall and ends well past it.
+        let sweep = Sweep {
+            position0: Vec2::new(-5.0, 0.0),
+            angle0: 0.0,
+            position: Vec2::new(5.0, 0.0),
+            angle: 0.3,
+        };
+        let out = time_of_impact(&bullet, &sweep, &wall, &Sweep::default(), 1.0, TARGET);
+        assert_eq!(out.state, ToiState::Touching);
+        assert_relative_eq!(out.normal.x, 1.0, epsilon = 1e-3);
+
+        let gap = distance(
+            &bullet,
+            &sweep.transform(out.t),
+            &wall,
+            &Transform::default(),
+            &mut SimplexCache::default(),
+        );
+        assert!(gap.distance > 0.0 && gap.distance < 1.25 * TARGET);
+
+        // Moving away never touches.
+        let away = Sweep {
+            position: Vec2::new(-9.0, 0.0),
+            ..sweep
+        };
+        let out = time_of_impact(&bullet, &away, &wall, &Sweep::default(), 1.0, TARGET);
+        assert_eq!(out.state, ToiState::Separated);
+    }
+}
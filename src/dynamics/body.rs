@@ -1,28 +1,103 @@
-use crate::math::Vec2;
+use crate::collision::{Aabb, MassData, Shape};
+use crate::dynamics::{Fixture, FixtureDef, JointHandle, Material};
+use crate::math::{Mat22, Transform, Vec2};
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum BodyType {
+    /// Never moves.
+    #[default]
+    Static,
+    /// Moved only by its velocity: infinite mass, no gravity, and contacts
+    /// and joints do not push it back.
+    Kinematic,
+    Dynamic,
+}
+
+/// Which bodies may collide, as in Box2D. Two bodies collide when each one's
+/// category is in the other's mask, unless they share a group: a positive
+/// group always collides and a negative one never does.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Filter {
+    pub category_bits: u16,
+    pub mask_bits: u16,
+    pub group_index: i16,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            category_bits: 0x0001,
+            mask_bits: 0xFFFF,
+            group_index: 0,
+        }
+    }
+}
+
+impl Filter {
+    pub fn should_collide(&self, other: &Filter) -> bool {
+        if self.group_index == other.group_index && self.group_index != 0 {
+            return self.group_index > 0;
+        }
+        (self.mask_bits & other.category_bits) != 0 && (self.category_bits & other.mask_bits) != 0
+    }
+}
+
+#[derive(Clone, Debug)]
 pub struct BodyDef {
     pub width: Vec2,
+    /// `None` => box of `width`.
+    pub shape: Option<Shape>,
+    /// Shapes making up the body. When empty, the body gets a single fixture
+    /// from `shape`, `friction`, `restitution`, `material` and `density`.
+    pub fixtures: Vec<FixtureDef>,
     pub position: Vec2,
     pub rotation: f32,
     pub friction: f32,
-    pub mass: Option<f32>, // None => static
+    pub restitution: f32,
+    /// `None` => the default material with `friction` and `restitution`.
+    /// When set, those two fields are ignored.
+    pub material: Option<Material>,
+    pub density: f32,
+    /// Total mass of a dynamic body, spread over the fixtures in proportion
+    /// to their density. `None` => density times area, summed over the
+    /// fixtures, or 1 if that is zero.
+    pub mass: Option<f32>,
+    /// `None` => dynamic if `mass` is set, static otherwise. Static and
+    /// kinematic bodies ignore `mass`.
+    pub body_type: Option<BodyType>,
+    /// Sweep this body against everything it passes, not just where it
+    /// ends up, so it cannot tunnel through thin geometry in one step.
+    pub bullet: bool,
+    /// Detect overlaps without colliding. Overlaps with non-sensor bodies
+    /// are reported as [`SensorEvent`](crate::dynamics::SensorEvent)s.
+    pub is_sensor: bool,
+    pub filter: Filter,
 }
 
 impl Default for BodyDef {
     fn default() -> Self {
         Self {
             width: Vec2::new(1.0, 1.0),
+            shape: None,
+            fixtures: Vec::new(),
             position: Vec2::new(0.0, 0.0),
             rotation: 0.0,
             friction: 0.2,
+            restitution: 0.0,
+            material: None,
+            density: 1.0,
             mass: None,
+            body_type: None,
+            bullet: false,
+            is_sensor: false,
+            filter: Filter::default(),
         }
     }
 }
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Clone, Debug)]
 pub struct Body {
+    /// Center of mass; fixtures are placed relative to it.
     pub position: Vec2,
     pub rotation: f32,
 
@@ -32,58 +107,223 @@ pub struct Body {
     pub force: Vec2,
     pub torque: f32,
 
+    /// Local bounding box size; the box itself for box bodies.
     pub width: Vec2,
+    pub fixtures: Vec<Fixture>,
+    pub(crate) joints: Vec<JointHandle>,
 
-    pub friction: f32,
+    pub body_type: BodyType,
+    /// See [`BodyDef::bullet`].
+    pub bullet: bool,
+    /// See [`BodyDef::is_sensor`].
+    pub is_sensor: bool,
+    /// Read when the broad-phase pairs the body with another, so changes
+    /// only affect pairs formed afterwards.
+    pub filter: Filter,
     pub inv_mass: f32,
     pub inv_i: f32,
+
+    /// Seconds spent below the world's sleep tolerances.
+    pub sleep_time: f32,
+    awake: bool,
 }
 
 impl Body {
+    /// Wakes the body.
     #[inline]
     pub fn add_force(&mut self, f: Vec2) {
         self.force += f;
+        self.set_awake(true);
+    }
+
+    /// Sleeping bodies are neither integrated nor solved. Static bodies are
+    /// never awake.
+    #[inline]
+    pub fn is_awake(&self) -> bool {
+        self.awake
     }
 
+    /// Wake or put to sleep. Writing `velocity` or `force` directly does not
+    /// wake a body; use [`set_velocity`](Self::set_velocity) or call this
+    /// afterwards. Has no effect on static bodies.
+    pub fn set_awake(&mut self, flag: bool) {
+        if self.body_type == BodyType::Static {
+            return;
+        }
+        if flag {
+            if !self.awake {
+                self.awake = true;
+                self.sleep_time = 0.0;
+            }
+        } else {
+            self.awake = false;
+            self.sleep_time = 0.0;
+            self.velocity.set(0.0, 0.0);
+            self.angular_velocity = 0.0;
+            self.force.set(0.0, 0.0);
+            self.torque = 0.0;
+        }
+    }
+
+    /// Joints attached to the body.
     #[inline]
+    pub fn joints(&self) -> &[JointHandle] {
+        &self.joints
+    }
+
+    /// Wakes the body unless the velocity is zero.
+    #[inline]
+    pub fn set_velocity(&mut self, v: Vec2) {
+        self.velocity = v;
+        if v.dot(v) > 0.0 {
+            self.set_awake(true);
+        }
+    }
+
+    /// Wakes the body unless `w` is zero.
+    #[inline]
+    pub fn set_angular_velocity(&mut self, w: f32) {
+        self.angular_velocity = w;
+        if w != 0.0 {
+            self.set_awake(true);
+        }
+    }
+
+    #[inline]
+    pub fn is_dynamic(&self) -> bool {
+        self.body_type == BodyType::Dynamic
+    }
+
+    #[inline]
+    pub fn transform(&self) -> Transform {
+        Transform::new(self.position, self.rotation)
+    }
+
+    /// World transform of fixture `index`.
+    #[inline]
+    pub fn fixture_transform(&self, index: usize) -> Transform {
+        self.transform() * self.fixtures[index].transform
+    }
+
+    /// Panics if a dynamic body has a segment or chain fixture. Those have no
+    /// inside and do not collide with each other, so they only go on static
+    /// and kinematic bodies.
     pub fn from_def(def: BodyDef) -> Self {
-        let (inv_mass, inv_i) = match def.mass {
-            // Dynamic body
-            Some(mass) => {
+        let mut fixtures: Vec<Fixture> = if def.fixtures.is_empty() {
+            vec![Fixture::from_def(FixtureDef {
+                shape: def.shape.unwrap_or(Shape::Box { width: def.width }),
+                friction: def.friction,
+                restitution: def.restitution,
+                material: def.material,
+                density: def.density,
+                ..Default::default()
+            })]
+        } else {
+            def.fixtures.into_iter().map(Fixture::from_def).collect()
+        };
+        let mut position = def.position;
+
+        // Mass properties of all fixtures, inertia about the center of mass.
+        let md = compute_mass(&fixtures);
+
+        // Move the body origin to the center of mass.
+        if md.center != Vec2::new(0.0, 0.0) {
+            for f in &mut fixtures {
+                f.transform.position -= md.center;
+            }
+            position += Mat22::from_angle(def.rotation) * md.center;
+        }
+
+        let body_type = def.body_type.unwrap_or(match def.mass {
+            Some(_) => BodyType::Dynamic,
+            None => BodyType::Static,
+        });
+        assert!(
+            body_type != BodyType::Dynamic
+                || !fixtures
+                    .iter()
+                    .any(|f| matches!(f.shape, Shape::Segment(_) | Shape::Chain(_))),
+            "segments and chains only go on static and kinematic bodies"
+        );
+
+        let (inv_mass, inv_i) = match body_type {
+            BodyType::Dynamic => {
+                let mass = def
+                    .mass
+                    .unwrap_or(if md.mass > 0.0 { md.mass } else { 1.0 });
                 debug_assert!(mass > 0.0 && mass.is_finite());
-                debug_assert!(def.width.x > 0.0 && def.width.y > 0.0);
 
                 let inv_mass = 1.0 / mass;
 
-                let i = mass * (def.width.x * def.width.x + def.width.y * def.width.y) / 12.0;
-                let inv_i = 1.0 / i;
+                // Scale the density-derived inertia to the requested mass.
+                // Without density there is no inertia to scale, and the body
+                // does not rotate.
+                let inv_i = if md.mass > 0.0 {
+                    md.mass / (md.i * mass)
+                } else {
+                    0.0
+                };
 
                 (inv_mass, inv_i)
             }
-            None => {
-                // Static body
-                (0.0, 0.0)
-            }
+            // Static and kinematic bodies
+            _ => (0.0, 0.0),
         };
 
+        let width = fixtures
+            .iter()
+            .map(|f| f.shape.compute_aabb(&f.transform))
+            .reduce(|a, b| a.union(&b))
+            .map_or(Vec2::new(0.0, 0.0), |aabb: Aabb| aabb.size());
+
         Self {
-            position: def.position,
+            position,
             rotation: def.rotation,
             velocity: Vec2::new(0.0, 0.0),
             angular_velocity: 0.0,
             force: Vec2::new(0.0, 0.0),
             torque: 0.0,
-            width: def.width,
-            friction: def.friction,
+            width,
+            fixtures,
+            joints: Vec::new(),
+            body_type,
+            bullet: def.bullet,
+            is_sensor: def.is_sensor,
+            filter: def.filter,
             inv_mass,
             inv_i,
+            sleep_time: 0.0,
+            awake: body_type != BodyType::Static,
         }
     }
 }
 
+/// Combined mass of `fixtures` in the body frame, `i` about the combined center.
+fn compute_mass(fixtures: &[Fixture]) -> MassData {
+    let mut mass = 0.0;
+    let mut center = Vec2::new(0.0, 0.0);
+    let mut i = 0.0;
+    for f in fixtures {
+        let md = f.shape.compute_mass(f.density);
+        let c = f.transform.apply(md.center);
+        mass += md.mass;
+        center += md.mass * c;
+        // Parallel axis theorem, about the body origin for now.
+        i += md.i + md.mass * c.dot(c);
+    }
+
+    if mass > 0.0 {
+        center = (1.0 / mass) * center;
+        i -= mass * center.dot(center);
+    }
+
+    MassData { mass, center, i }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::collision::{Polygon, Segment};
     use approx::assert_relative_eq;
 
     #[test]
@@ -101,6 +341,26 @@ mod tests {
         assert_relative_eq!(b.width.y, 4.0);
     }
 
+    #[test]
+    fn body_type_defaults_from_mass_and_kinematic_ignores_it() {
+        let b = Body::from_def(BodyDef::default());
+        assert_eq!(b.body_type, BodyType::Static);
+        let b = Body::from_def(BodyDef {
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        assert_eq!(b.body_type, BodyType::Dynamic);
+
+        let b = Body::from_def(BodyDef {
+            mass: Some(1.0),
+            body_type: Some(BodyType::Kinematic),
+            ..Default::default()
+        });
+        assert_eq!(b.body_type, BodyType::Kinematic);
+        assert_relative_eq!(b.inv_mass, 0.0);
+        assert_relative_eq!(b.inv_i, 0.0);
+    }
+
     #[test]
     fn body_from_def_dynamic_computes_inv_mass_and_inv_i() {
         let mass = 3.0;
@@ -121,6 +381,186 @@ mod tests {
         assert_relative_eq!(b.inv_i, 1.0 / i, epsilon = 1e-6);
     }
 
+    #[test]
+    fn body_from_def_dynamic_without_mass_uses_density() {
+        let w = Vec2::new(2.0, 4.0);
+        let b = Body::from_def(BodyDef {
+            width: w,
+            density: 2.0,
+            body_type: Some(BodyType::Dynamic),
+            ..Default::default()
+        });
+
+        assert_relative_eq!(b.inv_mass, 1.0 / 16.0, epsilon = 1e-6);
+        let i = 16.0 * (w.x * w.x + w.y * w.y) / 12.0;
+        assert_relative_eq!(b.inv_i, 1.0 / i, epsilon = 1e-6);
+
+        // Densities are ignored when the mass is given.
+        let b = Body::from_def(BodyDef {
+            width: w,
+            density: 2.0,
+            mass: Some(4.0),
+            ..Default::default()
+        });
+        assert_relative_eq!(b.inv_mass, 0.25, epsilon = 1e-6);
+    }
+
+    #[test]
+    fn body_from_def_circle_uses_disk_inertia() {
+        let b = Body::from_def(BodyDef {
+            shape: Some(Shape::Circle { radius: 0.5 }),
+            mass: Some(2.0),
+            ..Default::default()
+        });
+
+        // i = m*r^2/2
+        assert_relative_eq!(b.inv_i, 1.0 / (0.5 * 2.0 * 0.25), epsilon = 1e-5);
+        assert_relative_eq!(b.width.x, 1.0, epsilon = 1e-6);
+        assert_eq!(b.fixtures[0].shape, Shape::Circle { radius: 0.5 });
+    }
+
+    #[test]
+    #[should_panic(expected = "segments and chains")]
+    fn body_from_def_rejects_dynamic_segments() {
+        Body::from_def(BodyDef {
+            shape: Some(Shape::Segment(Segment::two_sided(
+                Vec2::new(-1.0, 0.0),
+                Vec2::new(1.0, 0.0),
+            ))),
+            mass: Some(2.0),
+            ..Default::default()
+        });
+    }
+
+    #[test]
+    fn body_from_def_dynamic_without_density_does_not_rotate() {
+        let b = Body::from_def(BodyDef {
+            density: 0.0,
+            mass: Some(2.0),
+            ..Default::default()
+        });
+
+        assert_relative_eq!(b.inv_mass, 0.5);
+        assert_eq!(b.inv_i, 0.0);
+    }
+
+    #[test]
+    fn body_from_def_polygon_is_recentered_on_centroid() {
+        let tri = Polygon::new(&[
+            Vec2::new(0.0, 0.0),
+            Vec2::new(3.0, 0.0),
+            Vec2::new(0.0, 3.0),
+        ]);
+        let b = Body::from_def(BodyDef {
+            shape: Some(Shape::Polygon(tri)),
+            position: Vec2::new(10.0, 0.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+
+        assert_relative_eq!(b.position.x, 11.0, epsilon = 1e-5);
+        assert_relative_eq!(b.position.y, 1.0, epsilon = 1e-5);
+        // The fixture is shifted so the centroid lands on the body origin.
+        let centroid = b.fixtures[0].shape.compute_mass(1.0).center;
+        let p = b.fixture_transform(0).apply(centroid);
+        assert_relative_eq!(p.x, b.position.x, epsilon = 1e-5);
+        assert_relative_eq!(p.y, b.position.y, epsilon = 1e-5);
+        assert!(b.inv_i > 0.0);
+    }
+
+    #[test]
+    fn body_from_def_compound_combines_fixtures() {
+        // Two unit boxes side by side, the right one twice as dense.
+        let unit = Shape::Box {
+            width: Vec2::new(1.0, 1.0),
+        };
+        let b = Body::from_def(BodyDef {
+            fixtures: vec![
+                FixtureDef {
+                    shape: unit.clone(),
+                    offset: Vec2::new(-0.5, 0.0),
+                    friction: 0.1,
+                    ..Default::default()
+                },
+                FixtureDef {
+                    shape: unit,
+                    offset: Vec2::new(0.5, 0.0),
+                    density: 2.0,
+                    friction: 0.9,
+                    ..Default::default()
+                },
+            ],
+            mass: Some(3.0),
+            ..Default::default()
+        });
+
+        // Center of mass at x = (1 * -0.5 + 2 * 0.5) / 3.
+        assert_relative_eq!(b.position.x, 1.0 / 6.0, epsilon = 1e-5);
+        assert_relative_eq!(
+            b.fixtures[0].transform.position.x,
+            -2.0 / 3.0,
+            epsilon = 1e-5
+        );
+        assert_relative_eq!(b.fixtures[1].material.friction, 0.9);
+        assert_relative_eq!(b.width.x, 2.0, epsilon = 1e-5);
+
+        // i = sum(m/6 + m*d^2) with masses 1 and 2 at -2/3 and 1/3.
+        let i = (1.0 / 6.0 + 4.0 / 9.0) + (2.0 / 6.0 + 2.0 / 9.0);
+        assert_relative_eq!(b.inv_mass, 1.0 / 3.0, epsilon = 1e-6);
+        assert_relative_eq!(b.inv_i, 1.0 / i, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn body_from_def_material_overrides_friction_and_restitution() {
+        let b = Body::from_def(BodyDef {
+            friction: 0.7,
+            restitution: 0.5,
+            ..Default::default()
+        });
+        assert_relative_eq!(b.fixtures[0].material.friction, 0.7);
+        assert_relative_eq!(b.fixtures[0].material.restitution, 0.5);
+
+        let b = Body::from_def(BodyDef {
+            friction: 0.7,
+            material: Some(Material {
+                tangent_speed: 1.0,
+                ..Material::with_friction(0.1)
+            }),
+            ..Default::default()
+        });
+        assert_relative_eq!(b.fixtures[0].material.friction, 0.1);
+        assert_relative_eq!(b.fixtures[0].material.tangent_speed, 1.0);
+    }
+
+    #[test]
+    fn filter_groups_override_category_and_mask() {
+        let player = Filter {
+            category_bits: 0x0002,
+            mask_bits: !0x0004,
+            group_index: 0,
+        };
+        let pickup = Filter {
+            category_bits: 0x0004,
+            ..Filter::default()
+        };
+        assert!(player.should_collide(&Filter::default()));
+        assert!(!player.should_collide(&pickup) && !pickup.should_collide(&player));
+
+        let limb = Filter {
+            group_index: -1,
+            ..Filter::default()
+        };
+        assert!(!limb.should_collide(&limb));
+        assert!(limb.should_collide(&Filter::default()));
+
+        let always = Filter {
+            mask_bits: 0,
+            group_index: 3,
+            ..Filter::default()
+        };
+        assert!(always.should_collide(&always));
+    }
+
     #[test]
     fn add_force_accumulates() {
         let mut b = Body::from_def(BodyDef {
@@ -0,0 +1,123 @@
+use crate::collision::arbiter::{Contact, MAX_POINTS};
+use crate::collision::{Arbiter, ArbiterKey};
+use crate::dynamics::{Body, BodyHandle, SlotMap};
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum ContactEventKind {
+    /// The pair started touching this step.
+    Begin,
+    /// The pair was touching before and still is. Pairs of sleeping bodies
+    /// are not reported.
+    Persist,
+    /// The pair stopped touching, or one of its bodies was destroyed.
+    End,
+}
+
+/// A change in an arbiter, with a copy of its manifold at the time. End
+/// events carry the last manifold the pair had.
+#[derive(Copy, Clone, Debug)]
+pub struct ContactEvent {
+    pub kind: ContactEventKind,
+    pub key: ArbiterKey,
+    pub contacts: [Contact; MAX_POINTS],
+    pub num_contacts: usize,
+}
+
+impl ContactEvent {
+    pub fn new(kind: ContactEventKind, key: ArbiterKey, arb: &Arbiter) -> Self {
+        Self {
+            kind,
+            key,
+            contacts: arb.contacts,
+            num_contacts: arb.num_contacts,
+        }
+    }
+
+    #[inline]
+    pub fn body1(&self) -> BodyHandle {
+        self.key.body1
+    }
+
+    #[inline]
+    pub fn body2(&self) -> BodyHandle {
+        self.key.body2
+    }
+
+    #[inline]
+    pub fn contacts(&self) -> &[Contact] {
+        &self.contacts[..self.num_contacts]
+    }
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum SensorEventKind {
+    /// A body started overlapping the sensor.
+    Enter,
+    /// It stopped overlapping, or one of the two was destroyed.
+    Exit,
+}
+
+/// A body entering or leaving a sensor body, per fixture child pair.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct SensorEvent {
+    pub kind: SensorEventKind,
+    pub sensor: BodyHandle,
+    pub visitor: BodyHandle,
+    pub key: ArbiterKey,
+}
+
+impl SensorEvent {
+    /// Event for the pair `key`, one of whose bodies is a sensor.
+    pub fn new(kind: SensorEventKind, key: ArbiterKey, bodies: &SlotMap<BodyHandle, Body>) -> Self {
+        let (sensor, visitor) = if bodies[key.body1].is_sensor {
+            (key.body1, key.body2)
+        } else {
+            (key.body2, key.body1)
+        };
+        Self {
+            kind,
+            sensor,
+            visitor,
+            key,
+        }
+    }
+}
+
+/// Hooks into the contact solver, set with
+/// [`World::set_contact_listener`](crate::dynamics::World::set_contact_listener).
+/// The solve hooks only see touching arbiters with an awake body.
+pub trait ContactListener {
+    /// Called when the broad-phase pairs two bodies whose [`Filter`]s let
+    /// them collide. Return `false` to keep them apart. The same pair may
+    /// be offered again later.
+    ///
+    /// [`Filter`]: crate::dynamics::Filter
+    fn should_collide(
+        &mut self,
+        body1: BodyHandle,
+        body2: BodyHandle,
+        bodies: &SlotMap<BodyHandle, Body>,
+    ) -> bool {
+        let _ = (body1, body2, bodies);
+        true
+    }
+
+    /// Called after the narrow phase, before the arbiter's pre-step. The
+    /// arbiter may be changed, e.g. its friction; return `false` to leave
+    /// the contact out of this step, as for one-way platforms.
+    fn pre_solve(
+        &mut self,
+        key: &ArbiterKey,
+        arb: &mut Arbiter,
+        bodies: &SlotMap<BodyHandle, Body>,
+    ) -> bool {
+        let _ = (key, arb, bodies);
+        true
+    }
+
+    /// Called after the velocity iterations with the impulses (`pn`, `pt`)
+    /// the solver settled on. Not called for disabled contacts.
+    fn post_solve(&mut self, key: &ArbiterKey, arb: &Arbiter, bodies: &SlotMap<BodyHandle, Body>) {
+        let _ = (key, arb, bodies);
+    }
+}
@@ -0,0 +1,68 @@
+use crate::collision::{ProxyId, Shape};
+use crate::dynamics::Material;
+use crate::math::{Transform, Vec2};
+
+/// A shape to attach to a body.
+#[derive(Clone, Debug)]
+pub struct FixtureDef {
+    pub shape: Shape,
+    /// Placement of the shape in the body frame.
+    pub offset: Vec2,
+    pub angle: f32,
+    pub friction: f32,
+    /// How much of the approach speed a contact gives back, from 0 (no
+    /// bounce) to 1 (elastic).
+    pub restitution: f32,
+    /// `None` => the default material with `friction` and `restitution`.
+    /// When set, those two fields are ignored.
+    pub material: Option<Material>,
+    pub density: f32,
+}
+
+impl Default for FixtureDef {
+    fn default() -> Self {
+        Self {
+            shape: Shape::Box {
+                width: Vec2::new(1.0, 1.0),
+            },
+            offset: Vec2::new(0.0, 0.0),
+            angle: 0.0,
+            friction: 0.2,
+            restitution: 0.0,
+            material: None,
+            density: 1.0,
+        }
+    }
+}
+
+/// A shape attached to a body.
+#[derive(Clone, Debug)]
+pub struct Fixture {
+    pub shape: Shape,
+    /// Shape frame relative to the body frame, whose origin is the body's
+    /// center of mass.
+    pub transform: Transform,
+    pub material: Material,
+    pub density: f32,
+    /// Broad-phase proxy of each collision child, set by the world.
+    pub proxies: Vec<ProxyId>,
+}
+
+impl Fixture {
+    #[inline]
+    pub fn from_def(def: FixtureDef) -> Self {
+        debug_assert!(def.density >= 0.0 && def.density.is_finite());
+
+        Self {
+            shape: def.shape,
+            transform: Transform::new(def.offset, def.angle),
+            material: def.material.unwrap_or(Material {
+                friction: def.friction,
+                restitution: def.restitution,
+                ..Default::default()
+            }),
+            density: def.density,
+            proxies: Vec::new(),
+        }
+    }
+}
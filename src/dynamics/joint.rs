@@ -1,4 +1,4 @@
-use crate::dynamics::{Body, BodyHandle, World, WorldConfig, bodies_two_mut};
+use crate::dynamics::{Body, BodyHandle, SlotMap, World, WorldConfig, bodies_two_mut};
 use crate::math::{Mat22, Vec2};
 
 #[derive(Copy, Clone, Debug)]
@@ -8,6 +8,9 @@ pub struct JointDef {
     pub anchor: Vec2, // 世界坐标锚点
     pub softness: f32,
     pub bias_factor: f32,
+    /// Whether the two bodies still collide with each other. Off by default,
+    /// as links of a chain usually overlap at their anchors.
+    pub collide_connected: bool,
 }
 
 impl JointDef {
@@ -18,6 +21,7 @@ impl JointDef {
             anchor,
             softness: 0.0,
             bias_factor: 0.2,
+            collide_connected: false,
         }
     }
 }
@@ -32,11 +36,35 @@ pub struct Joint {
     p: Vec2,
     body1: BodyHandle,
     body2: BodyHandle,
-    bias_factor: f32,
-    softness: f32,
+    pub bias_factor: f32,
+    pub softness: f32,
+    collide_connected: bool,
 }
 
 impl Joint {
+    #[inline]
+    pub fn bodies(&self) -> (BodyHandle, BodyHandle) {
+        (self.body1, self.body2)
+    }
+
+    /// Anchor in each body's frame.
+    #[inline]
+    pub fn local_anchors(&self) -> (Vec2, Vec2) {
+        (self.local_anchor1, self.local_anchor2)
+    }
+
+    /// Impulse accumulated over the last step.
+    #[inline]
+    pub fn impulse(&self) -> Vec2 {
+        self.p
+    }
+
+    /// Fixed at creation; see [`JointDef::collide_connected`].
+    #[inline]
+    pub fn collide_connected(&self) -> bool {
+        self.collide_connected
+    }
+
     #[inline]
     pub fn endpoints(&self, world: &World) -> (Vec2, Vec2) {
         let b1 = world.body(self.body1);
@@ -80,8 +108,8 @@ impl Joint {
 
         Self {
             m: Mat22::default(),
-            local_anchor1: local_anchor1,
-            local_anchor2: local_anchor2,
+            local_anchor1,
+            local_anchor2,
             r1: Vec2::default(),
             r2: Vec2::default(),
             bias: Vec2::default(),
@@ -90,9 +118,14 @@ impl Joint {
             body2: def.body2,
             bias_factor: def.bias_factor,
             softness: def.softness,
+            collide_connected: def.collide_connected,
         }
     }
 
+    /// Re-anchor a joint that is not in a world yet. The world's joint
+    /// lists and contact pairs are not updated, so move a joint that is
+    /// already in one with [`World::destroy_joint`] and
+    /// [`World::create_joint`] instead.
     pub fn set(&mut self, world: &World, b1: BodyHandle, b2: BodyHandle, anchor: Vec2) {
         self.body1 = b1;
         self.body2 = b2;
@@ -112,7 +145,12 @@ impl Joint {
         self.bias_factor = 0.2;
     }
 
-    pub fn pre_step(&mut self, inv_dt: f32, bodies: &mut [Body], config: &WorldConfig) {
+    pub fn pre_step(
+        &mut self,
+        inv_dt: f32,
+        bodies: &mut SlotMap<BodyHandle, Body>,
+        config: &WorldConfig,
+    ) {
         // Cache configs, latter world will be mutably borrowed.
         let (body1, body2) = bodies_two_mut(bodies, self.body1, self.body2);
 
@@ -176,7 +214,7 @@ impl Joint {
         }
     }
 
-    pub fn apply_impulse(&mut self, bodies: &mut [Body]) {
+    pub fn apply_impulse(&mut self, bodies: &mut SlotMap<BodyHandle, Body>) {
         let (body1, body2) = bodies_two_mut(bodies, self.body1, self.body2);
 
         let dv = body2.velocity + Vec2::cross_scalar_vec(body2.angular_velocity, self.r2)
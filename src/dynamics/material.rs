@@ -0,0 +1,161 @@
+/// How the values of two touching materials are mixed.
+///
+/// When the two materials ask for different rules, the one listed later
+/// here is used.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, PartialOrd, Ord)]
+pub enum CombineRule {
+    Average,
+    Min,
+    Max,
+    Multiply,
+    GeometricMean,
+}
+
+impl CombineRule {
+    #[inline]
+    pub fn combine(self, a: f32, b: f32) -> f32 {
+        match self {
+            CombineRule::Average => 0.5 * (a + b),
+            CombineRule::Min => a.min(b),
+            CombineRule::Max => a.max(b),
+            CombineRule::Multiply => a * b,
+            CombineRule::GeometricMean => (a * b).sqrt(),
+        }
+    }
+}
+
+/// A rule for each mixed material property.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct CombineRules {
+    pub friction: CombineRule,
+    pub restitution: CombineRule,
+    pub rolling_resistance: CombineRule,
+}
+
+impl Default for CombineRules {
+    /// Box2D's mixing: anything bouncy bounces, and a frictionless surface
+    /// slides against everything.
+    fn default() -> Self {
+        Self {
+            friction: CombineRule::GeometricMean,
+            restitution: CombineRule::Max,
+            rolling_resistance: CombineRule::Max,
+        }
+    }
+}
+
+/// Surface properties of a fixture.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Material {
+    pub friction: f32,
+    /// How much of the approach speed a contact gives back, from 0 (no
+    /// bounce) to 1 (elastic).
+    pub restitution: f32,
+    /// Resists rolling with a torque of this times the normal force and the
+    /// rounding radius of the shapes. Polygons without rounding do not roll.
+    pub rolling_resistance: f32,
+    /// Speed of the surface along itself, clockwise around the body, as for
+    /// conveyor belts. The speeds of two touching surfaces add up.
+    pub tangent_speed: f32,
+    /// `None` => [`WorldConfig::combine_rules`](crate::dynamics::WorldConfig::combine_rules).
+    pub combine_rules: Option<CombineRules>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            friction: 0.2,
+            restitution: 0.0,
+            rolling_resistance: 0.0,
+            tangent_speed: 0.0,
+            combine_rules: None,
+        }
+    }
+}
+
+impl Material {
+    /// The default material with `friction`.
+    #[inline]
+    pub fn with_friction(friction: f32) -> Self {
+        Self {
+            friction,
+            ..Default::default()
+        }
+    }
+
+    /// Mix with the material of the other side of a contact. The result
+    /// has no rules of its own.
+    pub fn combine(&self, other: &Material, default_rules: &CombineRules) -> Material {
+        let rules = match (self.combine_rules, other.combine_rules) {
+            (None, None) => *default_rules,
+            (Some(rules), None) | (None, Some(rules)) => rules,
+            (Some(a), Some(b)) => CombineRules {
+                friction: a.friction.max(b.friction),
+                restitution: a.restitution.max(b.restitution),
+                rolling_resistance: a.rolling_resistance.max(b.rolling_resistance),
+            },
+        };
+
+        Material {
+            friction: rules.friction.combine(self.friction, other.friction),
+            restitution: rules
+                .restitution
+                .combine(self.restitution, other.restitution),
+            rolling_resistance: rules
+                .rolling_resistance
+                .combine(self.rolling_resistance, other.rolling_resistance),
+            tangent_speed: self.tangent_speed + other.tangent_speed,
+            combine_rules: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn combine_uses_world_rules_unless_overridden() {
+        let ice = Material::with_friction(0.04);
+        let rubber = Material {
+            friction: 1.0,
+            restitution: 0.8,
+            ..Default::default()
+        };
+        let world = CombineRules::default();
+
+        let mixed = ice.combine(&rubber, &world);
+        assert_relative_eq!(mixed.friction, 0.2, epsilon = 1e-6);
+        assert_relative_eq!(mixed.restitution, 0.8);
+
+        // Ice stays slippery against anything.
+        let ice = Material {
+            combine_rules: Some(CombineRules {
+                friction: CombineRule::Min,
+                ..world
+            }),
+            ..ice
+        };
+        assert_relative_eq!(ice.combine(&rubber, &world).friction, 0.04);
+        assert_relative_eq!(rubber.combine(&ice, &world).friction, 0.04);
+
+        // Conflicting rules: the later one wins.
+        let averaging = Material {
+            combine_rules: Some(CombineRules {
+                friction: CombineRule::Average,
+                ..world
+            }),
+            ..rubber
+        };
+        assert_relative_eq!(ice.combine(&averaging, &world).friction, 0.04);
+        let multiplying = Material {
+            combine_rules: Some(CombineRules {
+                friction: CombineRule::Multiply,
+                ..world
+            }),
+            ..Material::with_friction(0.5)
+        };
+        assert_relative_eq!(multiplying.combine(&ice, &world).friction, 0.02);
+    }
+}
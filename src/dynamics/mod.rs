@@ -1,7 +1,17 @@
 pub mod body;
+pub mod events;
+pub mod fixture;
 pub mod joint;
+pub mod material;
+pub mod query;
+pub mod slot_map;
 pub mod world;
 
-pub use body::{Body, BodyDef};
+pub use body::{Body, BodyDef, BodyType, Filter};
+pub use events::{ContactEvent, ContactEventKind, ContactListener, SensorEvent, SensorEventKind};
+pub use fixture::{Fixture, FixtureDef};
 pub use joint::{Joint, JointDef};
-pub use world::{BodyHandle, World, WorldConfig, bodies_two_mut};
+pub use material::{CombineRule, CombineRules, Material};
+pub use query::{RayCastHit, ShapeCastHit};
+pub use slot_map::{SlotKey, SlotMap};
+pub use world::{BodyHandle, ConfigError, JointHandle, World, WorldConfig, bodies_two_mut};
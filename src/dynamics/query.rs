@@ -0,0 +1,538 @@
+use crate::collision::{
+    Aabb, ConvexProxy, FixtureProxy, RayCastInput, Shape, SimplexCache, Sweep, ToiState, distance,
+    time_of_impact,
+};
+use crate::dynamics::{BodyHandle, World};
+use crate::math::{Transform, Vec2};
+
+/// A fixture hit by [`World::ray_cast`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct RayCastHit {
+    pub body: BodyHandle,
+    pub fixture: usize,
+    /// Collision child of the fixture; the edge index for chains.
+    pub child: usize,
+    pub point: Vec2,
+    /// Unit surface normal at `point`.
+    pub normal: Vec2,
+    /// Position of `point` along the ray, as a fraction of `direction`.
+    pub fraction: f32,
+}
+
+/// First fixture hit by [`World::shape_cast`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ShapeCastHit {
+    pub body: BodyHandle,
+    pub fixture: usize,
+    pub child: usize,
+    /// Point on the fixture's surface.
+    pub point: Vec2,
+    /// Unit normal out of the fixture towards the cast shape; zero when the
+    /// shape starts out overlapping.
+    pub normal: Vec2,
+    /// How far along `translation` the shape can move before touching.
+    pub fraction: f32,
+}
+
+impl World {
+    /// Cast a ray from `origin` along `direction`, up to
+    /// `origin + max_fraction * direction`, and report each fixture it enters.
+    ///
+    /// Hits arrive in no particular order. The callback's return value picks
+    /// what happens next, as in Box2D:
+    /// - `hit.fraction` clips the ray there; the last hit reported is then the
+    ///   closest (see [`ray_cast_closest`](Self::ray_cast_closest)),
+    /// - `0.0` stops at the first hit found (any-hit),
+    /// - `max_fraction` keeps the ray as it is, so every hit is reported
+    ///   (all-hits),
+    /// - a negative value ignores the hit.
+    ///
+    /// Sensor bodies are not hit.
+    pub fn ray_cast(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_fraction: f32,
+        mut callback: impl FnMut(&RayCastHit) -> f32,
+    ) {
+        let input = RayCastInput {
+            p1: origin,
+            p2: origin + direction,
+            max_fraction,
+        };
+        self.proxies.ray_cast(&input, &mut |sub_input, proxy| {
+            let body = &self.bodies[proxy.body];
+            if body.is_sensor {
+                return sub_input.max_fraction;
+            }
+            let shape = body.fixtures[proxy.fixture].shape.child(proxy.child);
+            match shape.ray_cast(sub_input, &body.fixture_transform(proxy.fixture)) {
+                Some(out) => callback(&RayCastHit {
+                    body: proxy.body,
+                    fixture: proxy.fixture,
+                    child: proxy.child,
+                    point: origin + out.fraction * direction,
+                    normal: out.normal,
+                    fraction: out.fraction,
+                }),
+                None => sub_input.max_fraction,
+            }
+        });
+    }
+
+    /// The hit nearest to `origin`.
+    pub fn ray_cast_closest(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_fraction: f32,
+    ) -> Option<RayCastHit> {
+        let mut closest = None;
+        self.ray_cast(origin, direction, max_fraction, |hit| {
+            closest = Some(*hit);
+            hit.fraction
+        });
+        closest
+    }
+
+    /// Some hit, if there is one; cheaper than the closest for line of sight.
+    pub fn ray_cast_any(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_fraction: f32,
+    ) -> Option<RayCastHit> {
+        let mut any = None;
+        self.ray_cast(origin, direction, max_fraction, |hit| {
+            any = Some(*hit);
+            0.0
+        });
+        any
+    }
+
+    /// Every hit, nearest first.
+    pub fn ray_cast_all(
+        &self,
+        origin: Vec2,
+        direction: Vec2,
+        max_fraction: f32,
+    ) -> Vec<RayCastHit> {
+        let mut hits = Vec::new();
+        self.ray_cast(origin, direction, max_fraction, |hit| {
+            hits.push(*hit);
+            max_fraction
+        });
+        hits.sort_by(|a, b| a.fraction.total_cmp(&b.fraction));
+        hits
+    }
+
+    /// Call `callback` with each fixture child whose shape overlaps `aabb`.
+    /// Return `false` from the callback to stop the query.
+    pub fn query_aabb(&self, aabb: &Aabb, mut callback: impl FnMut(FixtureProxy) -> bool) {
+        let region =
+            ConvexProxy::from_child(&Shape::Box { width: aabb.size() }, 0, &Transform::default());
+        let region_xf = Transform::new(aabb.center(), 0.0);
+        self.query_region(aabb, &region, &region_xf, &mut callback);
+    }
+
+    /// Bodies with a fixture containing `p`, in handle order.
+    pub fn query_point(&self, p: Vec2) -> Vec<BodyHandle> {
+        let point =
+            ConvexProxy::from_child(&Shape::Circle { radius: 0.0 }, 0, &Transform::default());
+        let mut bodies = Vec::new();
+        self.query_region(
+            &Aabb::new(p, p),
+            &point,
+            &Transform::new(p, 0.0),
+            &mut |proxy| {
+                bodies.push(proxy.body);
+                true
+            },
+        );
+        bodies.sort();
+        bodies.dedup();
+        bodies
+    }
+
+    /// Sweep `shape`, posed by `transform`, along `translation` and return the
+    /// first fixture it would touch. The reported fraction leaves the shape
+    /// [`WorldConfig::toi_target`](crate::dynamics::WorldConfig::toi_target)
+    /// short of contact, so moving it there keeps it separated. A shape that
+    /// already overlaps something hits at fraction zero. Chains are cast edge
+    /// by edge.
+    ///
+    /// Fixture children for which `filter` returns `false` are skipped, such
+    /// as those of the body doing the cast. Sensor bodies are skipped too, as
+    /// in continuous collision, so the shape can move into them.
+    pub fn shape_cast(
+        &self,
+        shape: &Shape,
+        transform: &Transform,
+        translation: Vec2,
+        mut filter: impl FnMut(FixtureProxy) -> bool,
+    ) -> Option<ShapeCastHit> {
+        // Rotation goes into the proxy so the sweep is a pure translation.
+        let rotation = Transform {
+            position: Vec2::new(0.0, 0.0),
+            rotation: transform.rotation,
+        };
+        let sweep = Sweep {
+            position0: transform.position,
+            angle0: 0.0,
+            position: transform.position + translation,
+            angle: 0.0,
+        };
+
+        let mut first: Option<ShapeCastHit> = None;
+        for child in 0..shape.child_count() {
+            let cast = ConvexProxy::from_child(shape, child, &rotation);
+            let start = shape.compute_child_aabb(child, transform);
+            let end = Aabb::new(start.lower + translation, start.upper + translation);
+
+            let mut overlapped = false;
+            self.proxies.query(&start.union(&end), &mut |proxy| {
+                let body = &self.bodies[proxy.body];
+                if body.is_sensor || !filter(proxy) {
+                    return true;
+                }
+                let fixture = &body.fixtures[proxy.fixture];
+                let target =
+                    ConvexProxy::from_child(&fixture.shape, proxy.child, &fixture.transform);
+                let t_max = first.map_or(1.0, |hit| hit.fraction);
+
+                let out = time_of_impact(
+                    &target,
+                    &Sweep::fixed(body.position, body.rotation),
+                    &cast,
+                    &sweep,
+                    t_max,
+                    self.config.toi_target,
+                );
+                let hit = |normal| ShapeCastHit {
+                    body: proxy.body,
+                    fixture: proxy.fixture,
+                    child: proxy.child,
+                    point: out.point,
+                    normal,
+                    fraction: out.t,
+                };
+                match out.state {
+                    ToiState::Overlapped => {
+                        first = Some(hit(Vec2::new(0.0, 0.0)));
+                        overlapped = true;
+                        return false;
+                    }
+                    ToiState::Touching => {
+                        first = Some(hit(out.normal));
+                    }
+                    _ => {}
+                }
+                true
+            });
+            if overlapped {
+                break;
+            }
+        }
+        first
+    }
+
+    /// Broad-phase candidates for `aabb`, kept if their shape actually
+    /// touches `region`.
+    fn query_region(
+        &self,
+        aabb: &Aabb,
+        region: &ConvexProxy,
+        region_xf: &Transform,
+        callback: &mut dyn FnMut(FixtureProxy) -> bool,
+    ) {
+        self.proxies.query(aabb, &mut |proxy| {
+            let body = &self.bodies[proxy.body];
+            let fixture = &body.fixtures[proxy.fixture];
+            let shape = ConvexProxy::from_child(&fixture.shape, proxy.child, &fixture.transform);
+            let out = distance(
+                &shape,
+                &body.transform(),
+                region,
+                region_xf,
+                &mut SimplexCache::default(),
+            );
+            out.distance > 0.0 || callback(proxy)
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collision::{BroadPhaseKind, Chain};
+    use crate::dynamics::{BodyDef, WorldConfig};
+    use approx::assert_relative_eq;
+
+    /// Boxes at x = 2, 4, 6 on the x axis, the middle one rotated.
+    fn row(kind: BroadPhaseKind) -> (World, Vec<BodyHandle>) {
+        let mut world = World::with_config(
+            Vec2::new(0.0, 0.0),
+            10,
+            WorldConfig {
+                broad_phase: kind,
+                ..WorldConfig::default()
+            },
+        );
+        let handles = [2.0, 4.0, 6.0]
+            .iter()
+            .map(|&x| {
+                world.create_body(BodyDef {
+                    width: Vec2::new(1.0, 1.0),
+                    position: Vec2::new(x, 0.0),
+                    rotation: if x == 4.0 { 0.5 } else { 0.0 },
+                    ..Default::default()
+                })
+            })
+            .collect();
+        (world, handles)
+    }
+
+    #[test]
+    fn closest_any_and_all_hits_agree_across_broad_phases() {
+        for kind in [
+            BroadPhaseKind::DynamicTree,
+            BroadPhaseKind::SweepAndPrune,
+            BroadPhaseKind::BruteForce,
+        ] {
+            let (world, handles) = row(kind);
+            let (origin, direction) = (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+
+            let closest = world.ray_cast_closest(origin, direction, 1.0).unwrap();
+            assert_eq!(closest.body, handles[0]);
+            assert_relative_eq!(closest.point.x, 1.5, epsilon = 1e-5);
+            assert_relative_eq!(closest.normal.x, -1.0, epsilon = 1e-5);
+            assert_relative_eq!(closest.fraction, 0.15, epsilon = 1e-5);
+
+            let all = world.ray_cast_all(origin, direction, 1.0);
+            let bodies: Vec<BodyHandle> = all.iter().map(|hit| hit.body).collect();
+            assert_eq!(bodies, handles);
+            // The rotated box is entered on a tilted face.
+            assert!(all[1].normal.y.abs() > 0.1);
+
+            assert!(world.ray_cast_any(origin, direction, 1.0).is_some());
+            // Clipped short of the first box.
+            assert!(world.ray_cast_any(origin, direction, 0.1).is_none());
+            assert_eq!(world.ray_cast_all(origin, direction, 0.3).len(), 1);
+        }
+    }
+
+    #[test]
+    fn all_hits_stay_within_max_fraction() {
+        for kind in [
+            BroadPhaseKind::DynamicTree,
+            BroadPhaseKind::SweepAndPrune,
+            BroadPhaseKind::BruteForce,
+        ] {
+            let mut world = World::with_config(
+                Vec2::new(0.0, 0.0),
+                10,
+                WorldConfig {
+                    broad_phase: kind,
+                    ..WorldConfig::default()
+                },
+            );
+            for k in 1..=20 {
+                world.create_body(BodyDef {
+                    position: Vec2::new(2.0 * k as f32, 0.0),
+                    ..Default::default()
+                });
+            }
+
+            // Both ways, the ray ends 5 m in, past the second box's near face.
+            for (origin, direction) in [
+                (Vec2::new(0.0, 0.0), Vec2::new(50.0, 0.0)),
+                (Vec2::new(42.0, 0.0), Vec2::new(-50.0, 0.0)),
+            ] {
+                let all = world.ray_cast_all(origin, direction, 0.1);
+                assert_eq!(all.len(), 2, "{kind:?} from {origin:?}");
+                assert!(all.iter().all(|hit| hit.fraction <= 0.1));
+            }
+        }
+    }
+
+    #[test]
+    fn negative_return_filters_hits() {
+        let (world, handles) = row(BroadPhaseKind::DynamicTree);
+        let mut nearest = None;
+        world.ray_cast(Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0), 1.0, |hit| {
+            if hit.body == handles[0] {
+                return -1.0;
+            }
+            nearest = Some(hit.body);
+            hit.fraction
+        });
+        assert_eq!(nearest, Some(handles[1]));
+    }
+
+    #[test]
+    fn overlap_queries_test_the_rotated_box_not_its_bounds() {
+        let (world, handles) = row(BroadPhaseKind::DynamicTree);
+
+        // Inside the middle box's bounding box, but off its rotated corner.
+        let corner = Vec2::new(4.6, 0.6);
+        assert!(world.query_point(corner).is_empty());
+        assert_eq!(world.query_point(Vec2::new(4.2, 0.2)), vec![handles[1]]);
+
+        let mut found = Vec::new();
+        world.query_aabb(
+            &Aabb::new(Vec2::new(4.55, 0.55), Vec2::new(5.0, 1.0)),
+            |proxy| {
+                found.push(proxy.body);
+                true
+            },
+        );
+        assert!(found.is_empty());
+
+        world.query_aabb(
+            &Aabb::new(Vec2::new(1.0, -0.1), Vec2::new(5.0, 0.1)),
+            |proxy| {
+                found.push(proxy.body);
+                true
+            },
+        );
+        found.sort();
+        assert_eq!(found, handles[..2]);
+
+        // Stopping after the first fixture.
+        let mut count = 0;
+        world.query_aabb(
+            &Aabb::new(Vec2::new(0.0, -1.0), Vec2::new(10.0, 1.0)),
+            |_| {
+                count += 1;
+                false
+            },
+        );
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn shape_cast_stops_at_the_first_box() {
+        let (world, handles) = row(BroadPhaseKind::DynamicTree);
+        let probe = Shape::Box {
+            width: Vec2::new(0.5, 0.5),
+        };
+
+        // Sweeping right along the row hits the first box's left face.
+        let hit = world
+            .shape_cast(
+                &probe,
+                &Transform::new(Vec2::new(0.0, 0.0), 0.0),
+                Vec2::new(10.0, 0.0),
+                |_| true,
+            )
+            .unwrap();
+        assert_eq!(hit.body, handles[0]);
+        assert_relative_eq!(hit.fraction, 0.125, epsilon = 1e-3);
+        assert_relative_eq!(hit.normal.x, -1.0, epsilon = 1e-3);
+        assert_relative_eq!(hit.point.x, 1.5, epsilon = 1e-3);
+
+        // A rotated probe reaches the face sooner, by its half diagonal.
+        let hit = world
+            .shape_cast(
+                &probe,
+                &Transform::new(Vec2::new(0.0, 0.0), core::f32::consts::FRAC_PI_4),
+                Vec2::new(10.0, 0.0),
+                |_| true,
+            )
+            .unwrap();
+        let half_diagonal = 0.25 * 2.0_f32.sqrt();
+        assert_relative_eq!(hit.fraction, (1.5 - half_diagonal) / 10.0, epsilon = 1e-3);
+
+        // Passing above the row, and starting inside a box.
+        let above = Transform::new(Vec2::new(0.0, 1.0), 0.0);
+        assert!(
+            world
+                .shape_cast(&probe, &above, Vec2::new(10.0, 0.0), |_| true)
+                .is_none()
+        );
+        let inside = Transform::new(Vec2::new(6.0, 0.0), 0.0);
+        let hit = world
+            .shape_cast(&probe, &inside, Vec2::new(1.0, 0.0), |_| true)
+            .unwrap();
+        assert_eq!((hit.body, hit.fraction), (handles[2], 0.0));
+    }
+
+    #[test]
+    fn shape_cast_skips_filtered_bodies() {
+        let (mut world, handles) = row(BroadPhaseKind::DynamicTree);
+        let shape = Shape::Box {
+            width: Vec2::new(0.5, 0.5),
+        };
+        let me = world.create_body(BodyDef {
+            shape: Some(shape.clone()),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let xf = world.body(me).transform();
+
+        // A character sweeping its own box starts inside itself.
+        let hit = world
+            .shape_cast(&shape, &xf, Vec2::new(10.0, 0.0), |_| true)
+            .unwrap();
+        assert_eq!((hit.body, hit.fraction), (me, 0.0));
+
+        let hit = world
+            .shape_cast(&shape, &xf, Vec2::new(10.0, 0.0), |proxy| proxy.body != me)
+            .unwrap();
+        assert_eq!(hit.body, handles[0]);
+        assert_relative_eq!(hit.fraction, 0.125, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn casts_pass_through_sensors() {
+        let (mut world, handles) = row(BroadPhaseKind::DynamicTree);
+        world.create_body(BodyDef {
+            width: Vec2::new(0.5, 1.0),
+            position: Vec2::new(1.0, 0.0),
+            is_sensor: true,
+            ..Default::default()
+        });
+        let (origin, direction) = (Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0));
+
+        let closest = world.ray_cast_closest(origin, direction, 1.0).unwrap();
+        assert_eq!(closest.body, handles[0]);
+        assert_eq!(world.ray_cast_all(origin, direction, 1.0).len(), 3);
+
+        let probe = Shape::Box {
+            width: Vec2::new(0.5, 0.5),
+        };
+        let hit = world
+            .shape_cast(&probe, &Transform::new(origin, 0.0), direction, |_| true)
+            .unwrap();
+        assert_eq!(hit.body, handles[0]);
+        assert_relative_eq!(hit.fraction, 0.125, epsilon = 1e-3);
+    }
+
+    #[test]
+    fn shape_cast_sweeps_chains_edge_by_edge() {
+        let (world, handles) = row(BroadPhaseKind::DynamicTree);
+        let arrow = Shape::Chain(Chain::new_open(
+            vec![
+                Vec2::new(-0.25, -0.25),
+                Vec2::new(0.25, 0.0),
+                Vec2::new(-0.25, 0.25),
+            ],
+            Vec2::new(-0.5, -0.5),
+            Vec2::new(-0.5, 0.5),
+        ));
+        assert!(ConvexProxy::new(&arrow, &Transform::default()).is_none());
+
+        // The tip, shared by both edges, reaches the first box.
+        let hit = world
+            .shape_cast(
+                &arrow,
+                &Transform::new(Vec2::new(0.0, 0.0), 0.0),
+                Vec2::new(10.0, 0.0),
+                |_| true,
+            )
+            .unwrap();
+        assert_eq!(hit.body, handles[0]);
+        assert_relative_eq!(hit.fraction, 0.125, epsilon = 1e-3);
+        assert_relative_eq!(hit.normal.x, -1.0, epsilon = 1e-3);
+    }
+}
@@ -0,0 +1,229 @@
+use std::ops::{Index, IndexMut};
+
+/// Handle into a [`SlotMap`]: a slot index plus the generation of the value
+/// it was issued for.
+pub trait SlotKey: Copy {
+    fn from_parts(index: u32, generation: u32) -> Self;
+
+    fn index(self) -> usize;
+
+    fn generation(self) -> u32;
+}
+
+#[derive(Clone, Debug)]
+struct Slot<T> {
+    generation: u32,
+    value: Option<T>,
+}
+
+/// Storage with stable handles. Removing a value bumps its slot's
+/// generation, so old handles to that slot are detected as stale instead of
+/// reaching whatever is stored there next.
+#[derive(Clone, Debug)]
+pub struct SlotMap<K, T> {
+    slots: Vec<Slot<T>>,
+    free_slots: Vec<u32>,
+    len: usize,
+    _key: std::marker::PhantomData<K>,
+}
+
+impl<K: SlotKey, T> Default for SlotMap<K, T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: SlotKey, T> SlotMap<K, T> {
+    pub fn new() -> Self {
+        Self {
+            slots: Vec::new(),
+            free_slots: Vec::new(),
+            len: 0,
+            _key: std::marker::PhantomData,
+        }
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// The key the next [`insert`](Self::insert) will return.
+    pub fn next_key(&self) -> K {
+        match self.free_slots.last() {
+            Some(&index) => K::from_parts(index, self.slots[index as usize].generation),
+            None => K::from_parts(self.slots.len() as u32, 0),
+        }
+    }
+
+    pub fn insert(&mut self, value: T) -> K {
+        let key = self.next_key();
+        match self.free_slots.pop() {
+            Some(index) => self.slots[index as usize].value = Some(value),
+            None => self.slots.push(Slot {
+                generation: 0,
+                value: Some(value),
+            }),
+        }
+        self.len += 1;
+        key
+    }
+
+    /// Remove and return the value, or `None` if `key` is stale.
+    pub fn remove(&mut self, key: K) -> Option<T> {
+        let slot = self.slots.get_mut(key.index())?;
+        if slot.generation != key.generation() {
+            return None;
+        }
+        let value = slot.value.take()?;
+        slot.generation = slot.generation.wrapping_add(1);
+        self.free_slots.push(key.index() as u32);
+        self.len -= 1;
+        Some(value)
+    }
+
+    #[inline]
+    pub fn contains(&self, key: K) -> bool {
+        self.get(key).is_some()
+    }
+
+    #[inline]
+    pub fn get(&self, key: K) -> Option<&T> {
+        let slot = self.slots.get(key.index())?;
+        if slot.generation != key.generation() {
+            return None;
+        }
+        slot.value.as_ref()
+    }
+
+    #[inline]
+    pub fn get_mut(&mut self, key: K) -> Option<&mut T> {
+        let slot = self.slots.get_mut(key.index())?;
+        if slot.generation != key.generation() {
+            return None;
+        }
+        slot.value.as_mut()
+    }
+
+    /// Mutable access to two distinct values.
+    pub fn get2_mut(&mut self, a: K, b: K) -> Option<(&mut T, &mut T)> {
+        assert!(a.index() != b.index(), "get2_mut called with the same slot");
+        if !self.contains(a) || !self.contains(b) {
+            return None;
+        }
+
+        let (i, j) = (a.index().min(b.index()), a.index().max(b.index()));
+        let (left, right) = self.slots.split_at_mut(j);
+        let vi = left[i].value.as_mut()?;
+        let vj = right[0].value.as_mut()?;
+        if a.index() < b.index() {
+            Some((vi, vj))
+        } else {
+            Some((vj, vi))
+        }
+    }
+
+    /// Keep only the values for which `f` returns `true`.
+    pub fn retain(&mut self, mut f: impl FnMut(K, &mut T) -> bool) {
+        let keys: Vec<K> = self.keys().collect();
+        for key in keys {
+            if !f(key, &mut self[key]) {
+                self.remove(key);
+            }
+        }
+    }
+
+    /// Remove every value. Existing keys become stale.
+    pub fn clear(&mut self) {
+        for (index, slot) in self.slots.iter_mut().enumerate() {
+            if slot.value.take().is_some() {
+                slot.generation = slot.generation.wrapping_add(1);
+                self.free_slots.push(index as u32);
+            }
+        }
+        self.len = 0;
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (K, &T)> {
+        self.slots.iter().enumerate().filter_map(|(index, slot)| {
+            let key = K::from_parts(index as u32, slot.generation);
+            slot.value.as_ref().map(|v| (key, v))
+        })
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (K, &mut T)> {
+        self.slots
+            .iter_mut()
+            .enumerate()
+            .filter_map(|(index, slot)| {
+                let key = K::from_parts(index as u32, slot.generation);
+                slot.value.as_mut().map(|v| (key, v))
+            })
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = K> + '_ {
+        self.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &T> {
+        self.slots.iter().filter_map(|slot| slot.value.as_ref())
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut T> {
+        self.slots.iter_mut().filter_map(|slot| slot.value.as_mut())
+    }
+}
+
+impl<K: SlotKey, T> Index<K> for SlotMap<K, T> {
+    type Output = T;
+
+    #[inline]
+    fn index(&self, key: K) -> &T {
+        self.get(key).expect("stale handle")
+    }
+}
+
+impl<K: SlotKey, T> IndexMut<K> for SlotMap<K, T> {
+    #[inline]
+    fn index_mut(&mut self, key: K) -> &mut T {
+        self.get_mut(key).expect("stale handle")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamics::BodyHandle;
+
+    #[test]
+    fn removed_keys_go_stale_when_the_slot_is_reused() {
+        let mut map: SlotMap<BodyHandle, &str> = SlotMap::new();
+        let a = map.insert("a");
+        let b = map.insert("b");
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.remove(a), Some("a"));
+        assert_eq!(map.remove(a), None);
+        assert!(!map.contains(a));
+
+        // Same slot, new generation.
+        let c = map.insert("c");
+        assert_eq!(c.index(), a.index());
+        assert_ne!(c, a);
+        assert_eq!(map.get(a), None);
+        assert_eq!(map[c], "c");
+
+        let (vb, vc) = map.get2_mut(b, c).unwrap();
+        std::mem::swap(vb, vc);
+        assert_eq!(map.values().copied().collect::<Vec<_>>(), vec!["b", "c"]);
+
+        map.clear();
+        assert!(map.is_empty());
+        assert!(!map.contains(b) && !map.contains(c));
+    }
+}
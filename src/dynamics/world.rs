@@ -1,22 +1,100 @@
-use crate::collision::{Arbiter, ArbiterKey};
-use crate::dynamics::{Body, BodyDef, Joint, JointDef};
-use crate::math::Vec2;
-use std::collections::BTreeMap;
+use crate::collision::{
+    Aabb, Arbiter, ArbiterKey, BroadPhase, BroadPhaseKind, ConvexProxy, FaceTolerance,
+    FixtureProxy, ProxyId, SimplexCache, Sweep, ToiOutput, ToiState, distance, time_of_impact,
+};
+use crate::dynamics::{
+    Body, BodyDef, BodyType, CombineRules, ContactEvent, ContactEventKind, ContactListener, Joint,
+    JointDef, SensorEvent, SensorEventKind, SlotKey, SlotMap,
+};
+use crate::math::{Transform, Vec2};
 use std::collections::btree_map::Entry;
+use std::collections::{BTreeMap, BTreeSet};
 
-#[repr(transparent)]
+/// Refers to a body until it is destroyed; see [`SlotMap`].
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
-pub struct BodyHandle(pub usize);
+pub struct BodyHandle {
+    index: u32,
+    generation: u32,
+}
+
+impl SlotKey for BodyHandle {
+    #[inline]
+    fn from_parts(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        self.index as usize
+    }
+
+    #[inline]
+    fn generation(self) -> u32 {
+        self.generation
+    }
+}
 
-#[repr(transparent)]
+/// Refers to a joint until it, or one of its bodies, is destroyed.
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, PartialOrd, Ord)]
-pub struct JointHandle(pub usize);
+pub struct JointHandle {
+    index: u32,
+    generation: u32,
+}
+
+impl SlotKey for JointHandle {
+    #[inline]
+    fn from_parts(index: u32, generation: u32) -> Self {
+        Self { index, generation }
+    }
+
+    #[inline]
+    fn index(self) -> usize {
+        self.index as usize
+    }
+
+    #[inline]
+    fn generation(self) -> u32 {
+        self.generation
+    }
+}
 
 #[derive(Copy, Clone, Debug)]
 pub struct WorldConfig {
     pub accumulate_impulses: bool,
     pub warm_starting: bool,
     pub position_correction: bool,
+    /// Penetration left for contacts to keep touching between steps. A
+    /// length, so scale it with the world's units.
+    pub allowed_penetration: f32,
+    /// Fraction of the remaining penetration pushed out per step, from 0
+    /// to 1. Joints have their own [`JointDef::bias_factor`].
+    pub bias_factor: f32,
+    /// Most a contact is pushed out in one step; deep overlaps are resolved
+    /// over several steps rather than launching the bodies apart. Unbounded
+    /// by default.
+    pub max_linear_correction: f32,
+    pub face_tolerance: FaceTolerance,
+    /// Contacts approaching slower than this do not bounce, so that resting
+    /// bodies settle.
+    pub restitution_threshold: f32,
+    /// Mixing for fixtures whose [`Material`](crate::dynamics::Material) has
+    /// no rules of its own.
+    pub combine_rules: CombineRules,
+    /// Read when the world is created; see also [`World::with_broad_phase`].
+    pub broad_phase: BroadPhaseKind,
+    /// Margin added around proxy AABBs so small motions don't touch the
+    /// broad-phase. A length; read when the world is created.
+    pub aabb_margin: f32,
+    /// Separation continuous collision and shape casts stop short of
+    /// contact. A length, so scale it with the world's units.
+    pub toi_target: f32,
+    /// Put islands to sleep once all their bodies have been slower than the
+    /// tolerances below for `time_to_sleep` seconds.
+    pub allow_sleeping: bool,
+    pub linear_sleep_tolerance: f32,
+    /// Radians per second.
+    pub angular_sleep_tolerance: f32,
+    pub time_to_sleep: f32,
 }
 
 impl Default for WorldConfig {
@@ -25,51 +103,260 @@ impl Default for WorldConfig {
             accumulate_impulses: true,
             warm_starting: true,
             position_correction: true,
+            allowed_penetration: 0.01,
+            bias_factor: 0.2,
+            max_linear_correction: f32::INFINITY,
+            face_tolerance: FaceTolerance::default(),
+            restitution_threshold: 1.0,
+            combine_rules: CombineRules::default(),
+            broad_phase: BroadPhaseKind::DynamicTree,
+            aabb_margin: 0.1,
+            toi_target: 0.005,
+            allow_sleeping: true,
+            linear_sleep_tolerance: 0.01,
+            angular_sleep_tolerance: 2.0_f32.to_radians(),
+            time_to_sleep: 0.5,
+        }
+    }
+}
+
+impl WorldConfig {
+    /// Check that the tuning values are in range.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let non_negative = |v: f32| (0.0..f32::INFINITY).contains(&v);
+        let checks = [
+            (
+                "allowed_penetration",
+                non_negative(self.allowed_penetration),
+            ),
+            ("bias_factor", (0.0..=1.0).contains(&self.bias_factor)),
+            ("max_linear_correction", self.max_linear_correction > 0.0),
+            (
+                "face_tolerance.relative_tol",
+                self.face_tolerance.relative_tol > 0.0 && self.face_tolerance.relative_tol <= 1.0,
+            ),
+            (
+                "face_tolerance.absolute_tol",
+                non_negative(self.face_tolerance.absolute_tol),
+            ),
+            ("aabb_margin", non_negative(self.aabb_margin)),
+            (
+                "toi_target",
+                self.toi_target > 0.0 && self.toi_target.is_finite(),
+            ),
+            (
+                "restitution_threshold",
+                non_negative(self.restitution_threshold),
+            ),
+            (
+                "linear_sleep_tolerance",
+                non_negative(self.linear_sleep_tolerance),
+            ),
+            (
+                "angular_sleep_tolerance",
+                non_negative(self.angular_sleep_tolerance),
+            ),
+            ("time_to_sleep", non_negative(self.time_to_sleep)),
+        ];
+        match checks.into_iter().find(|&(_, ok)| !ok) {
+            Some((field, _)) => Err(ConfigError { field }),
+            None => Ok(()),
         }
     }
 }
 
+/// A [`WorldConfig`] value out of range.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct ConfigError {
+    pub field: &'static str,
+}
+
+impl std::fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "world config `{}` is out of range", self.field)
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 pub struct World {
     pub gravity: Vec2,
     pub iterations: u32,
     pub config: WorldConfig,
-    pub bodies: Vec<Body>,
-    pub joints: Vec<Joint>,
+    pub bodies: SlotMap<BodyHandle, Body>,
+    pub joints: SlotMap<JointHandle, Joint>,
     pub arbiters: BTreeMap<ArbiterKey, Arbiter>,
+    /// Fixture proxies of all bodies.
+    pub proxies: Box<dyn BroadPhase>,
+    /// Pairs whose fat AABBs overlap; a superset of `arbiters`' keys.
+    pub pairs: BTreeSet<ArbiterKey>,
+    /// Pairs with a sensor whose shapes overlap. Sensors get no arbiters.
+    pub sensor_overlaps: BTreeSet<ArbiterKey>,
+    /// Events of the last step, until drained.
+    contact_events: Vec<ContactEvent>,
+    sensor_events: Vec<SensorEvent>,
+    /// End events from bodies destroyed since the last step.
+    pending_events: Vec<ContactEvent>,
+    pending_sensor_events: Vec<SensorEvent>,
+    contact_listener: Option<Box<dyn ContactListener>>,
 }
 
 impl World {
     #[inline]
     pub fn new(gravity: Vec2, iterations: u32) -> Self {
-        Self {
-            bodies: Vec::new(),
-            joints: Vec::new(),
-            arbiters: BTreeMap::new(),
+        Self::with_config(gravity, iterations, WorldConfig::default())
+    }
+
+    /// Panics if `config` fails [`WorldConfig::validate`]; see
+    /// [`World::try_with_config`].
+    pub fn with_config(gravity: Vec2, iterations: u32, config: WorldConfig) -> Self {
+        Self::try_with_config(gravity, iterations, config).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`World::with_config`], but returns the validation error instead
+    /// of panicking.
+    pub fn try_with_config(
+        gravity: Vec2,
+        iterations: u32,
+        config: WorldConfig,
+    ) -> Result<Self, ConfigError> {
+        config.validate()?;
+        let broad_phase = config.broad_phase.create(config.aabb_margin);
+        Ok(Self::with_broad_phase(
             gravity,
             iterations,
-            config: WorldConfig::default(),
-        }
+            config,
+            broad_phase,
+        ))
     }
 
-    pub fn with_config(gravity: Vec2, iterations: u32, config: WorldConfig) -> Self {
+    /// World using a custom broad-phase; `config.broad_phase` and
+    /// `config.aabb_margin` are ignored.
+    ///
+    /// Panics if `config` fails [`WorldConfig::validate`].
+    pub fn with_broad_phase(
+        gravity: Vec2,
+        iterations: u32,
+        config: WorldConfig,
+        broad_phase: Box<dyn BroadPhase>,
+    ) -> Self {
+        if let Err(err) = config.validate() {
+            panic!("{err}");
+        }
+        debug_assert!(
+            broad_phase.proxy_count() == 0,
+            "broad-phase already holds proxies"
+        );
         Self {
+            bodies: SlotMap::new(),
+            joints: SlotMap::new(),
+            arbiters: BTreeMap::new(),
+            proxies: broad_phase,
+            pairs: BTreeSet::new(),
+            sensor_overlaps: BTreeSet::new(),
+            contact_events: Vec::new(),
+            sensor_events: Vec::new(),
+            pending_events: Vec::new(),
+            pending_sensor_events: Vec::new(),
+            contact_listener: None,
+            gravity,
+            iterations,
             config,
-            ..Self::new(gravity, iterations)
         }
     }
 
+    /// Panics if `def` is rejected by [`Body::from_def`].
     pub fn create_body(&mut self, def: BodyDef) -> BodyHandle {
-        let id = self.bodies.len();
-        self.bodies.push(Body::from_def(def));
-        BodyHandle(id)
+        let handle = self.bodies.next_key();
+        let mut body = Body::from_def(def);
+
+        for k in 0..body.fixtures.len() {
+            let xf = body.fixture_transform(k);
+            let fixture = &mut body.fixtures[k];
+            for child in 0..fixture.shape.child_count() {
+                let aabb = fixture.shape.compute_child_aabb(child, &xf);
+                let proxy = FixtureProxy {
+                    body: handle,
+                    fixture: k,
+                    child,
+                };
+                fixture.proxies.push(self.proxies.create_proxy(aabb, proxy));
+            }
+        }
+
+        self.bodies.insert(body)
+    }
+
+    /// Remove a body along with its joints and contacts. Returns `None` if
+    /// the handle is stale.
+    pub fn destroy_body(&mut self, h: BodyHandle) -> Option<Body> {
+        let mut body = self.bodies.remove(h)?;
+
+        for fixture in &mut body.fixtures {
+            for proxy in fixture.proxies.drain(..) {
+                self.proxies.destroy_proxy(proxy);
+            }
+        }
+        // Whatever rested on or hung from the body has to wake up.
+        let bodies = &mut self.bodies;
+        self.joints.retain(|jh, joint| {
+            let (b1, b2) = joint.bodies();
+            let attached = b1 == h || b2 == h;
+            if attached {
+                let other = if b1 == h { b2 } else { b1 };
+                bodies[other].joints.retain(|&j| j != jh);
+                wake_other(bodies, b1, b2, h);
+            }
+            !attached
+        });
+        let pending_events = &mut self.pending_events;
+        self.arbiters.retain(|key, arb| {
+            let touching = key.body1 == h || key.body2 == h;
+            if touching {
+                wake_other(bodies, key.body1, key.body2, h);
+                pending_events.push(ContactEvent::new(ContactEventKind::End, *key, arb));
+            }
+            !touching
+        });
+        self.pairs.retain(|key| key.body1 != h && key.body2 != h);
+
+        let (sensor_overlaps, pending) =
+            (&mut self.sensor_overlaps, &mut self.pending_sensor_events);
+        sensor_overlaps.retain(|&key| {
+            if key.body1 != h && key.body2 != h {
+                return true;
+            }
+            // The destroyed body is no longer in the map to tell which side
+            // was the sensor.
+            let (sensor, visitor) = match (key.body1 == h, body.is_sensor) {
+                (true, true) | (false, false) => (key.body1, key.body2),
+                _ => (key.body2, key.body1),
+            };
+            pending.push(SensorEvent {
+                kind: SensorEventKind::Exit,
+                sensor,
+                visitor,
+                key,
+            });
+            false
+        });
+
+        Some(body)
     }
 
+    /// Panics if the handle is stale.
     pub fn body(&self, h: BodyHandle) -> &Body {
-        &self.bodies[h.0]
+        self.bodies.get(h).expect("stale body handle")
     }
 
+    /// Panics if the handle is stale.
     pub fn body_mut(&mut self, h: BodyHandle) -> &mut Body {
-        &mut self.bodies[h.0]
+        self.bodies.get_mut(h).expect("stale body handle")
+    }
+
+    #[inline]
+    pub fn contains_body(&self, h: BodyHandle) -> bool {
+        self.bodies.contains(h)
     }
 
     pub fn bodies_two_mut(&mut self, a: BodyHandle, b: BodyHandle) -> (&mut Body, &mut Body) {
@@ -77,54 +364,326 @@ impl World {
         bodies_two_mut(bodies, a, b)
     }
 
+    /// Unless [`JointDef::collide_connected`] is set, contacts the two bodies
+    /// already have are ended.
     pub fn create_joint(&mut self, def: JointDef) -> JointHandle {
-        let id = self.joints.len();
-        self.joints.push(Joint::from_def(&self, def));
-        JointHandle(id)
+        let joint = Joint::from_def(self, def);
+        let h = self.joints.insert(joint);
+        self.bodies[def.body1].joints.push(h);
+        self.bodies[def.body2].joints.push(h);
+        if !def.collide_connected {
+            self.unpair_bodies(def.body1, def.body2);
+        }
+        h
+    }
+
+    /// Remove a joint. Returns `None` if the handle is stale, which includes
+    /// joints removed along with one of their bodies.
+    pub fn destroy_joint(&mut self, h: JointHandle) -> Option<Joint> {
+        let joint = self.joints.remove(h)?;
+        let (b1, b2) = joint.bodies();
+        for b in [b1, b2] {
+            self.bodies[b].joints.retain(|&j| j != h);
+            self.bodies[b].set_awake(true);
+        }
+        if !joint.collide_connected() {
+            self.pair_bodies(b1, b2);
+        }
+        Some(joint)
+    }
+
+    /// Drop the pairs, contacts and sensor overlaps between two bodies.
+    /// Called outside a step, so end events wait for the next one.
+    fn unpair_bodies(&mut self, b1: BodyHandle, b2: BodyHandle) {
+        let pair = ArbiterKey::new(b1, b2);
+        let between = |key: &ArbiterKey| key.body1 == pair.body1 && key.body2 == pair.body2;
+
+        self.pairs.retain(|key| !between(key));
+        self.arbiters.retain(|key, arb| {
+            if between(key) {
+                let event = ContactEvent::new(ContactEventKind::End, *key, arb);
+                self.pending_events.push(event);
+            }
+            !between(key)
+        });
+        self.sensor_overlaps.retain(|&key| {
+            if between(&key) {
+                let event = SensorEvent::new(SensorEventKind::Exit, key, &self.bodies);
+                self.pending_sensor_events.push(event);
+            }
+            !between(&key)
+        });
+    }
+
+    /// Pair the overlapping proxies of two bodies now, as the broad-phase
+    /// would only report them once one of the proxies moves.
+    fn pair_bodies(&mut self, b1: BodyHandle, b2: BodyHandle) {
+        let listener = self.contact_listener.as_deref_mut();
+        if !should_pair(&self.bodies, &self.joints, listener, b1, b2) {
+            return;
+        }
+        for (k1, f1) in self.bodies[b1].fixtures.iter().enumerate() {
+            for (c1, &p1) in f1.proxies.iter().enumerate() {
+                for (k2, f2) in self.bodies[b2].fixtures.iter().enumerate() {
+                    for (c2, &p2) in f2.proxies.iter().enumerate() {
+                        if self.proxies.test_overlap(p1, p2) {
+                            let key = ArbiterKey::with_children(b1, (k1, c1), b2, (k2, c2));
+                            self.pairs.insert(key);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Panics if the handle is stale.
+    pub fn joint(&self, h: JointHandle) -> &Joint {
+        self.joints.get(h).expect("stale joint handle")
+    }
+
+    /// Panics if the handle is stale.
+    pub fn joint_mut(&mut self, h: JointHandle) -> &mut Joint {
+        self.joints.get_mut(h).expect("stale joint handle")
+    }
+
+    #[inline]
+    pub fn contains_joint(&self, h: JointHandle) -> bool {
+        self.joints.contains(h)
     }
 
     pub fn clear(&mut self) {
         self.bodies.clear();
         self.joints.clear();
         self.arbiters.clear();
+        self.proxies.clear();
+        self.pairs.clear();
+        self.sensor_overlaps.clear();
+        self.contact_events.clear();
+        self.sensor_events.clear();
+        self.pending_events.clear();
+        self.pending_sensor_events.clear();
+    }
+
+    /// Contact events from the last [`step`](Self::step), plus end events
+    /// for contacts of bodies destroyed before it. Each step replaces the
+    /// previous step's events, drained or not.
+    #[inline]
+    pub fn contact_events(&self) -> &[ContactEvent] {
+        &self.contact_events
+    }
+
+    /// Install hooks called around the contact solver, replacing any
+    /// previous listener.
+    pub fn set_contact_listener(&mut self, listener: Option<Box<dyn ContactListener>>) {
+        self.contact_listener = listener;
+    }
+
+    pub fn drain_contact_events(&mut self) -> impl Iterator<Item = ContactEvent> + '_ {
+        self.contact_events.drain(..)
+    }
+
+    /// Sensor events, kept like [`contact_events`](Self::contact_events).
+    #[inline]
+    pub fn sensor_events(&self) -> &[SensorEvent] {
+        &self.sensor_events
+    }
+
+    pub fn drain_sensor_events(&mut self) -> impl Iterator<Item = SensorEvent> + '_ {
+        self.sensor_events.drain(..)
     }
 
     pub fn broad_phase(&mut self) {
-        // O(n^2) broad-phase
-        let n = self.bodies.len();
-        for i in 0..n {
-            let bi = BodyHandle(i);
-            for j in i + 1..n {
-                let bj = BodyHandle(j);
-
-                if self.bodies[i].inv_mass == 0.0 && self.bodies[j].inv_mass == 0.0 {
-                    continue;
+        // Refit proxies to the current poses. Only those leaving their fat
+        // AABB are re-inserted and queried for new pairs.
+        for body in self.bodies.values() {
+            for (k, fixture) in body.fixtures.iter().enumerate() {
+                let xf = body.fixture_transform(k);
+                for (child, &proxy) in fixture.proxies.iter().enumerate() {
+                    let aabb = fixture.shape.compute_child_aabb(child, &xf);
+                    self.proxies.move_proxy(proxy, aabb);
                 }
+            }
+        }
+
+        let bodies = &self.bodies;
+        let joints = &self.joints;
+        let pairs = &mut self.pairs;
+        let mut listener = self.contact_listener.as_deref_mut();
+        self.proxies.update_pairs(&mut |a, b| {
+            if should_pair(bodies, joints, listener.as_deref_mut(), a.body, b.body) {
+                pairs.insert(ArbiterKey::with_children(
+                    a.body,
+                    (a.fixture, a.child),
+                    b.body,
+                    (b.fixture, b.child),
+                ));
+            }
+        });
+
+        // Narrow phase on pairs whose fat AABBs still overlap. Contacts
+        // between sleeping (or static) bodies are left as they are.
+        let mut pairs = std::mem::take(&mut self.pairs);
+        pairs.retain(|&key| {
+            if !self.bodies[key.body1].is_awake() && !self.bodies[key.body2].is_awake() {
+                return true;
+            }
+            let proxy1 = self.proxy_id(key.body1, key.fixture1, key.child1);
+            let proxy2 = self.proxy_id(key.body2, key.fixture2, key.child2);
+            let is_sensor = self.bodies[key.body1].is_sensor || self.bodies[key.body2].is_sensor;
+            let overlap = self.proxies.test_overlap(proxy1, proxy2);
+            match (is_sensor, overlap) {
+                (false, true) => self.update_arbiter(key),
+                (false, false) => self.remove_arbiter(key),
+                (true, _) => self.update_sensor(key, overlap),
+            }
+            overlap
+        });
+        self.pairs = pairs;
+    }
+
+    #[inline]
+    fn proxy_id(&self, body: BodyHandle, fixture: usize, child: usize) -> ProxyId {
+        self.bodies[body].fixtures[fixture].proxies[child]
+    }
+
+    fn update_arbiter(&mut self, key: ArbiterKey) {
+        let new_arb = Arbiter::from_key(key, self);
+
+        if new_arb.num_contacts > 0 {
+            match self.arbiters.entry(key) {
+                Entry::Vacant(e) => {
+                    let event = ContactEvent::new(ContactEventKind::Begin, key, &new_arb);
+                    self.contact_events.push(event);
+                    e.insert(new_arb);
+                    // A new contact wakes a sleeping body.
+                    self.bodies[key.body1].set_awake(true);
+                    self.bodies[key.body2].set_awake(true);
+                }
+                Entry::Occupied(mut e) => {
+                    let arb = e.get_mut();
+                    arb.update(
+                        &new_arb.contacts[..new_arb.num_contacts],
+                        self.config.warm_starting,
+                    );
+                    let event = ContactEvent::new(ContactEventKind::Persist, key, arb);
+                    self.contact_events.push(event);
+                }
+            }
+        } else {
+            self.remove_arbiter(key);
+        }
+    }
 
-                let new_arb = Arbiter::new(bi, bj, &self);
-                let key = ArbiterKey::new(bi, bj);
+    /// Track whether a sensor pair's shapes overlap, given whether their fat
+    /// AABBs still do.
+    fn update_sensor(&mut self, key: ArbiterKey, fat_overlap: bool) {
+        let overlap = fat_overlap && {
+            let (b1, b2) = (&self.bodies[key.body1], &self.bodies[key.body2]);
+            let (f1, f2) = (&b1.fixtures[key.fixture1], &b2.fixtures[key.fixture2]);
+            let out = distance(
+                &ConvexProxy::from_child(&f1.shape, key.child1, &f1.transform),
+                &b1.transform(),
+                &ConvexProxy::from_child(&f2.shape, key.child2, &f2.transform),
+                &b2.transform(),
+                &mut SimplexCache::default(),
+            );
+            out.distance == 0.0
+        };
 
-                if new_arb.num_contacts > 0 {
-                    match self.arbiters.entry(key) {
-                        Entry::Vacant(e) => {
-                            e.insert(new_arb);
+        let changed = if overlap {
+            self.sensor_overlaps.insert(key)
+        } else {
+            self.sensor_overlaps.remove(&key)
+        };
+        if changed {
+            let kind = if overlap {
+                SensorEventKind::Enter
+            } else {
+                SensorEventKind::Exit
+            };
+            self.sensor_events
+                .push(SensorEvent::new(kind, key, &self.bodies));
+        }
+    }
+
+    fn remove_arbiter(&mut self, key: ArbiterKey) {
+        if let Some(arb) = self.arbiters.remove(&key) {
+            let event = ContactEvent::new(ContactEventKind::End, key, &arb);
+            self.contact_events.push(event);
+        }
+    }
+
+    /// Groups of awake dynamic bodies connected through contacts and joints,
+    /// waking any sleeping dynamic body that they or an awake kinematic body
+    /// touch.
+    /// Static and kinematic bodies do not connect islands; a kinematic body
+    /// is listed in each island it touches, so that it keeps them awake
+    /// while it moves.
+    pub fn build_islands(&mut self) -> Vec<Vec<BodyHandle>> {
+        let mut edges: BTreeMap<BodyHandle, Vec<BodyHandle>> = BTreeMap::new();
+        let contacts = self.arbiters.keys().map(|k| (k.body1, k.body2));
+        let joints = self.joints.values().map(|j| j.bodies());
+        for (a, b) in contacts.chain(joints) {
+            edges.entry(a).or_default().push(b);
+            edges.entry(b).or_default().push(a);
+        }
+
+        // An awake kinematic body wakes everything it touches.
+        for (&h, others) in &edges {
+            let body = &self.bodies[h];
+            if body.body_type == BodyType::Kinematic && body.is_awake() {
+                for &other in others {
+                    self.bodies[other].set_awake(true);
+                }
+            }
+        }
+
+        let mut islands = Vec::new();
+        let mut visited = BTreeSet::new();
+        let seeds: Vec<BodyHandle> = self.bodies.keys().collect();
+        for seed in seeds {
+            let body = &self.bodies[seed];
+            if !body.is_dynamic() || !body.is_awake() || !visited.insert(seed) {
+                continue;
+            }
+
+            let mut island = Vec::new();
+            let mut kinematic = BTreeSet::new();
+            let mut stack = vec![seed];
+            while let Some(h) = stack.pop() {
+                self.bodies[h].set_awake(true);
+                island.push(h);
+
+                for &other in edges.get(&h).into_iter().flatten() {
+                    match self.bodies[other].body_type {
+                        BodyType::Dynamic => {
+                            if visited.insert(other) {
+                                stack.push(other);
+                            }
                         }
-                        Entry::Occupied(mut e) => {
-                            let arb = e.get_mut();
-                            arb.update(&new_arb.contacts[..new_arb.num_contacts], self.config.warm_starting);
+                        BodyType::Kinematic => {
+                            kinematic.insert(other);
                         }
+                        BodyType::Static => {}
                     }
-                } else {
-                    self.arbiters.remove(&key);
                 }
             }
+            island.extend(kinematic);
+            islands.push(island);
         }
+        islands
     }
 
     pub fn step(&mut self, dt: f32) {
         let inv_dt = if dt <= 0.0 { 0.0 } else { 1.0 / dt };
+        debug_assert!(self.config.validate().is_ok(), "invalid world config");
 
+        self.contact_events.clear();
+        self.contact_events.append(&mut self.pending_events);
+        self.sensor_events.clear();
+        self.sensor_events.append(&mut self.pending_sensor_events);
         self.broad_phase();
+        let islands = self.build_islands();
 
         // Split world so we can borrow parts at the same time.
         let World {
@@ -134,58 +693,282 @@ impl World {
             gravity,
             iterations,
             config,
+            contact_listener,
             ..
         } = self;
 
         // Integrate forces.
-        for b in &mut bodies.iter_mut() {
-            if b.inv_mass == 0.0 {
+        for b in bodies.values_mut() {
+            if !b.is_dynamic() || !b.is_awake() {
                 continue;
             }
             b.velocity += dt * (*gravity + b.inv_mass * b.force);
             b.angular_velocity += dt * b.inv_i * b.torque;
         }
 
+        // Let the listener adjust or disable contacts.
+        for (key, arb) in arbiters.iter_mut() {
+            arb.enabled = true;
+            if let Some(listener) = contact_listener.as_deref_mut()
+                && is_active(bodies, arb.body1, arb.body2)
+            {
+                arb.enabled = listener.pre_solve(key, arb, bodies);
+            }
+        }
+
         // Perform pre-steps.
         for arb in &mut arbiters.values_mut() {
-            arb.pre_step(inv_dt, bodies, config);
+            if arb.enabled && is_active(bodies, arb.body1, arb.body2) {
+                arb.pre_step(inv_dt, bodies, config);
+            }
         }
 
-        for joint in &mut joints.iter_mut() {
-            joint.pre_step(inv_dt, bodies, config);
+        for joint in joints.values_mut() {
+            let (b1, b2) = joint.bodies();
+            if is_active(bodies, b1, b2) {
+                joint.pre_step(inv_dt, bodies, config);
+            }
         }
 
         // Perform iterations
         for _ in 0..(*iterations as usize) {
             for arb in arbiters.values_mut() {
-                arb.apply_impulse(bodies, config);
+                if arb.enabled && is_active(bodies, arb.body1, arb.body2) {
+                    arb.apply_impulse(bodies, config);
+                }
             }
 
-            for joint in &mut joints.iter_mut() {
-                joint.apply_impulse(bodies);
+            for joint in joints.values_mut() {
+                let (b1, b2) = joint.bodies();
+                if is_active(bodies, b1, b2) {
+                    joint.apply_impulse(bodies);
+                }
+            }
+        }
+
+        if let Some(listener) = contact_listener.as_deref_mut() {
+            for (key, arb) in arbiters.iter() {
+                if arb.enabled && is_active(bodies, arb.body1, arb.body2) {
+                    listener.post_solve(key, arb, bodies);
+                }
             }
         }
 
+        // Remember where bullets start so they can be swept.
+        let bullets: Vec<(BodyHandle, Vec2, f32)> = bodies
+            .iter()
+            .filter(|(_, b)| b.bullet && !b.is_sensor && b.is_dynamic() && b.is_awake())
+            .map(|(h, b)| (h, b.position, b.rotation))
+            .collect();
+
         // Integrate Velocities.
-        for b in bodies {
-            b.position += dt * b.velocity;
-            b.rotation += dt * b.angular_velocity;
+        for b in bodies.values_mut() {
+            if b.is_awake() {
+                b.position += dt * b.velocity;
+                b.rotation += dt * b.angular_velocity;
+            }
 
             b.force.set(0.0, 0.0);
             b.torque = 0.0;
         }
+
+        self.solve_toi(&bullets);
+
+        if self.config.allow_sleeping {
+            update_sleep(&mut self.bodies, &islands, &self.config, dt);
+        }
+    }
+
+    /// Move each bullet back to its first impact along this step's sweep. The
+    /// discrete contacts take over from there next step.
+    fn solve_toi(&mut self, bullets: &[(BodyHandle, Vec2, f32)]) {
+        for &(h, position0, angle0) in bullets {
+            let body = &self.bodies[h];
+            let sweep = Sweep {
+                position0,
+                angle0,
+                position: body.position,
+                angle: body.rotation,
+            };
+            let swept = body_aabb(body, &sweep.transform(0.0))
+                .union(&body_aabb(body, &sweep.transform(1.0)));
+            let displacement = sweep.position - sweep.position0;
+
+            // Candidates come from the broad-phase, as of the last refit.
+            let mut first: Option<ToiOutput> = None;
+            let bodies = &self.bodies;
+            let joints = &self.joints;
+            let mut listener = self.contact_listener.as_deref_mut();
+            let toi_target = self.config.toi_target;
+            self.proxies.query(&swept, &mut |proxy| {
+                let other = &bodies[proxy.body];
+                // Only what the bullet would collide with can stop it.
+                if other.is_sensor
+                    || !should_pair(bodies, joints, listener.as_deref_mut(), h, proxy.body)
+                {
+                    return true;
+                }
+                let other_sweep = Sweep::fixed(other.position, other.rotation);
+                let fb = &other.fixtures[proxy.fixture];
+                let proxy_b = ConvexProxy::from_child(&fb.shape, proxy.child, &fb.transform);
+
+                for fa in &body.fixtures {
+                    for ca in 0..fa.shape.child_count() {
+                        let proxy_a = ConvexProxy::from_child(&fa.shape, ca, &fa.transform);
+                        let t_max = first.map_or(1.0, |hit| hit.t);
+                        let out = time_of_impact(
+                            &proxy_a,
+                            &sweep,
+                            &proxy_b,
+                            &other_sweep,
+                            t_max,
+                            toi_target,
+                        );
+                        // Grazing hits are not impacts.
+                        if out.state == ToiState::Touching
+                            && out.t < t_max
+                            && displacement.dot(out.normal) > 0.0
+                        {
+                            first = Some(out);
+                        }
+                    }
+                }
+                true
+            });
+
+            let Some(hit) = first else {
+                continue;
+            };
+
+            // Sink just into the surface so the discrete contact picks the
+            // hit up next step and resolves it with the other body. Staying
+            // within the allowed penetration, it is not pushed back out.
+            let approach = displacement.dot(hit.normal);
+            let sink = self.config.toi_target + 0.5 * self.config.allowed_penetration;
+            let t = (hit.t + sink / approach).min(1.0);
+
+            let bullet = &mut self.bodies[h];
+            bullet.position = sweep.position0 + t * displacement;
+            bullet.rotation = sweep.angle0 + t * (sweep.angle - sweep.angle0);
+        }
     }
 }
 
-pub fn bodies_two_mut(bodies: &mut [Body], a: BodyHandle, b: BodyHandle) -> (&mut Body, &mut Body) {
-    assert!(a != b, "bodies_two_mut called with identical handles");
+/// World bounding box of all of a body's fixtures at `xf`.
+fn body_aabb(body: &Body, xf: &Transform) -> Aabb {
+    body.fixtures
+        .iter()
+        .map(|f| f.shape.compute_aabb(&(*xf * f.transform)))
+        .reduce(|a, b| a.union(&b))
+        .unwrap_or_default()
+}
+
+/// Advance sleep timers; islands whose bodies all rested long enough sleep.
+/// Kinematic bodies also sleep on their own once they have stopped.
+fn update_sleep(
+    bodies: &mut SlotMap<BodyHandle, Body>,
+    islands: &[Vec<BodyHandle>],
+    config: &WorldConfig,
+    dt: f32,
+) {
+    let lin_tol_sq = config.linear_sleep_tolerance * config.linear_sleep_tolerance;
+    let ang_tol_sq = config.angular_sleep_tolerance * config.angular_sleep_tolerance;
+
+    for b in bodies.values_mut() {
+        if !b.is_awake() {
+            continue;
+        }
+        if b.velocity.dot(b.velocity) > lin_tol_sq
+            || b.angular_velocity * b.angular_velocity > ang_tol_sq
+        {
+            b.sleep_time = 0.0;
+        } else {
+            b.sleep_time += dt;
+        }
+    }
+
+    for island in islands {
+        let min_sleep_time = island
+            .iter()
+            .map(|&h| &bodies[h])
+            .filter(|b| b.is_awake())
+            .map(|b| b.sleep_time)
+            .fold(f32::MAX, f32::min);
+
+        if min_sleep_time >= config.time_to_sleep {
+            for &h in island {
+                bodies[h].set_awake(false);
+            }
+        }
+    }
+
+    for b in bodies.values_mut() {
+        if b.body_type == BodyType::Kinematic && b.sleep_time >= config.time_to_sleep {
+            b.set_awake(false);
+        }
+    }
+}
 
-    let (i, j) = if a.0 <= b.0 { (a.0, b.0) } else { (b.0, a.0) };
-    let (left, right) = bodies.split_at_mut(j);
-    let bi = &mut left[i];
-    let bj = &mut right[0];
+/// Whether a contact or joint between `a` and `b` needs solving.
+#[inline]
+fn is_active(bodies: &SlotMap<BodyHandle, Body>, a: BodyHandle, b: BodyHandle) -> bool {
+    bodies[a].is_awake() || bodies[b].is_awake()
+}
 
-    if a.0 < b.0 { (bi, bj) } else { (bj, bi) }
+/// Whether the broad-phase should pass a pair of bodies on to the narrow
+/// phase.
+fn should_pair(
+    bodies: &SlotMap<BodyHandle, Body>,
+    joints: &SlotMap<JointHandle, Joint>,
+    listener: Option<&mut (dyn ContactListener + '_)>,
+    a: BodyHandle,
+    b: BodyHandle,
+) -> bool {
+    if a == b {
+        return false;
+    }
+    let (body_a, body_b) = (&bodies[a], &bodies[b]);
+    // Only contacts with a dynamic body have any effect.
+    if !body_a.is_dynamic() && !body_b.is_dynamic() {
+        return false;
+    }
+    // Sensors do not detect each other.
+    if body_a.is_sensor && body_b.is_sensor {
+        return false;
+    }
+    if !body_a.filter.should_collide(&body_b.filter) {
+        return false;
+    }
+    let jointed = body_a.joints.iter().any(|&j| {
+        let joint = &joints[j];
+        let (b1, b2) = joint.bodies();
+        !joint.collide_connected() && (b1 == b || b2 == b)
+    });
+    if jointed {
+        return false;
+    }
+    listener.is_none_or(|listener| listener.should_collide(a, b, bodies))
+}
+
+/// Wake the body other than `destroyed` in a pair.
+#[inline]
+fn wake_other(
+    bodies: &mut SlotMap<BodyHandle, Body>,
+    a: BodyHandle,
+    b: BodyHandle,
+    destroyed: BodyHandle,
+) {
+    let other = if a == destroyed { b } else { a };
+    bodies[other].set_awake(true);
+}
+
+pub fn bodies_two_mut(
+    bodies: &mut SlotMap<BodyHandle, Body>,
+    a: BodyHandle,
+    b: BodyHandle,
+) -> (&mut Body, &mut Body) {
+    assert!(a != b, "bodies_two_mut called with identical handles");
+    bodies.get2_mut(a, b).expect("stale body handle")
 }
 
 #[cfg(test)]
@@ -276,4 +1059,368 @@ mod tests {
         assert_relative_eq!(b.force.y, 0.0, epsilon = 1e-6);
         assert_relative_eq!(b.torque, 0.0, epsilon = 1e-6);
     }
+
+    #[test]
+    fn broad_phase_pairs_only_nearby_bodies() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+        let ground = world.create_body(BodyDef {
+            width: Vec2::new(100.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            mass: None,
+            ..Default::default()
+        });
+        // A row of boxes, spaced apart, resting on the ground.
+        let boxes: Vec<BodyHandle> = (0..10)
+            .map(|k| {
+                world.create_body(BodyDef {
+                    position: Vec2::new(3.0 * k as f32, 0.5),
+                    mass: Some(1.0),
+                    ..Default::default()
+                })
+            })
+            .collect();
+
+        world.step(1.0 / 60.0);
+
+        // Each box pairs with the ground only, not with the other boxes.
+        assert_eq!(world.pairs.len(), 10);
+        assert!(world.pairs.iter().all(|k| k.body1 == ground));
+
+        // Moving one box away drops its pair.
+        world.body_mut(boxes[0]).position = Vec2::new(0.0, 10.0);
+        world.broad_phase();
+        assert_eq!(world.pairs.len(), 9);
+        assert!(!world.arbiters.keys().any(|k| k.body2 == boxes[0]));
+    }
+
+    #[test]
+    fn destroy_body_removes_joints_contacts_and_proxies() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+        let ground = world.create_body(BodyDef {
+            width: Vec2::new(20.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            mass: None,
+            ..Default::default()
+        });
+        let a = world.create_body(BodyDef {
+            position: Vec2::new(-1.0, 0.5),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let b = world.create_body(BodyDef {
+            position: Vec2::new(1.0, 0.5),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        world.create_joint(JointDef::new(a, b, Vec2::new(0.0, 0.5)));
+        world.step(1.0 / 60.0);
+        assert_eq!(world.arbiters.len(), 2);
+
+        assert!(world.destroy_body(a).is_some());
+        assert!(!world.contains_body(a));
+        assert!(world.joints.is_empty());
+        assert_eq!(world.proxies.proxy_count(), 2);
+        assert!(world.arbiters.keys().all(|k| k.body1 != a && k.body2 != a));
+        assert!(world.pairs.iter().all(|k| k.body1 != a && k.body2 != a));
+
+        // The slot is reused, but the old handle stays stale.
+        let c = world.create_body(BodyDef {
+            position: Vec2::new(-1.0, 0.5),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        assert_ne!(c, a);
+        assert!(world.destroy_body(a).is_none());
+        assert!(world.bodies.get(a).is_none());
+
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(world.arbiters.keys().all(|k| k.body1 == ground));
+        assert_eq!(world.arbiters.len(), 2);
+    }
+
+    #[test]
+    fn destroy_joint_invalidates_its_handle() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        let a = world.create_body(BodyDef {
+            mass: None,
+            ..Default::default()
+        });
+        let b = world.create_body(BodyDef {
+            position: Vec2::new(0.0, -2.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let c = world.create_body(BodyDef {
+            position: Vec2::new(0.0, -4.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let j1 = world.create_joint(JointDef::new(a, b, Vec2::new(0.0, -1.0)));
+        let j2 = world.create_joint(JointDef::new(b, c, Vec2::new(0.0, -3.0)));
+        assert_eq!(world.joint(j2).bodies().0, b);
+
+        world.joint_mut(j1).softness = 0.01;
+        assert_relative_eq!(world.joint(j1).softness, 0.01);
+
+        assert!(world.destroy_joint(j1).is_some());
+        assert!(!world.contains_joint(j1));
+        assert!(world.destroy_joint(j1).is_none());
+        assert_eq!(world.body(b).joints(), [j2]);
+
+        // A reused slot does not bring the old handle back.
+        let j3 = world.create_joint(JointDef::new(a, c, Vec2::new(0.0, -1.0)));
+        assert_ne!(j3, j1);
+        assert!(world.joints.get(j1).is_none());
+
+        // Destroying a body takes its joints with it.
+        world.destroy_body(c);
+        assert!(!world.contains_joint(j2) && !world.contains_joint(j3));
+        assert!(world.body(a).joints().is_empty() && world.body(b).joints().is_empty());
+    }
+
+    #[test]
+    fn joints_disable_collision_between_their_bodies() {
+        let mut world = World::new(Vec2::new(0.0, 0.0), 10);
+        let a = world.create_body(BodyDef {
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let b = world.create_body(BodyDef {
+            position: Vec2::new(0.8, 0.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        world.step(1.0 / 60.0);
+        assert_eq!(world.arbiters.len(), 1);
+        world.drain_contact_events().for_each(drop);
+
+        // A joint created after the contact ends it.
+        let anchor = Vec2::new(0.4, 0.0);
+        let j = world.create_joint(JointDef::new(a, b, anchor));
+        assert!(world.arbiters.is_empty() && world.pairs.is_empty());
+        world.step(1.0 / 60.0);
+        let kinds: Vec<_> = world.contact_events().iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![ContactEventKind::End]);
+        assert!(world.arbiters.is_empty());
+
+        // Still overlapping, so the contact comes back with the joint gone.
+        world.destroy_joint(j);
+        world.step(1.0 / 60.0);
+        assert_eq!(world.arbiters.len(), 1);
+
+        world.create_joint(JointDef {
+            collide_connected: true,
+            ..JointDef::new(a, b, anchor)
+        });
+        world.step(1.0 / 60.0);
+        assert_eq!(world.arbiters.len(), 1);
+    }
+
+    #[test]
+    fn resting_bodies_sleep_and_wake_on_force_or_removal() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        world.create_body(BodyDef {
+            width: Vec2::new(20.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            mass: None,
+            ..Default::default()
+        });
+        let bottom = world.create_body(BodyDef {
+            position: Vec2::new(0.0, 0.5),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let top = world.create_body(BodyDef {
+            position: Vec2::new(0.0, 1.5),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(!world.body(bottom).is_awake() && !world.body(top).is_awake());
+        assert_eq!(world.arbiters.len(), 2);
+
+        // A force wakes the whole stack, since it is one island.
+        world.body_mut(top).add_force(Vec2::new(0.1, 0.0));
+        world.step(1.0 / 60.0);
+        assert!(world.body(bottom).is_awake());
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(!world.body(top).is_awake());
+
+        // Pulling the bottom box out wakes the one resting on it.
+        world.destroy_body(bottom);
+        assert!(world.body(top).is_awake());
+        world.step(1.0 / 60.0);
+        assert!(world.body(top).velocity.y < 0.0);
+    }
+
+    #[test]
+    fn kinematic_bodies_do_not_join_islands() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        let platform = world.create_body(BodyDef {
+            width: Vec2::new(20.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            body_type: Some(BodyType::Kinematic),
+            ..Default::default()
+        });
+        let [resting, pushed] = [-5.0, 5.0].map(|x| {
+            world.create_body(BodyDef {
+                position: Vec2::new(x, 0.5),
+                mass: Some(1.0),
+                ..Default::default()
+            })
+        });
+
+        for _ in 0..60 {
+            world.body_mut(pushed).add_force(Vec2::new(5.0, 0.0));
+            world.step(1.0 / 60.0);
+        }
+        assert!(!world.body(resting).is_awake());
+        assert!(world.body(pushed).is_awake());
+
+        let islands = world.build_islands();
+        assert_eq!(islands, vec![vec![pushed, platform]]);
+    }
+
+    #[test]
+    fn kinematic_bodies_sleep_when_idle_and_wake_on_velocity() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        let elevator = world.create_body(BodyDef {
+            width: Vec2::new(4.0, 0.5),
+            body_type: Some(BodyType::Kinematic),
+            ..Default::default()
+        });
+        let rider = world.create_body(BodyDef {
+            position: Vec2::new(0.0, 0.75),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        assert!(!world.body(elevator).is_awake() && !world.body(rider).is_awake());
+
+        world.body_mut(elevator).set_velocity(Vec2::new(0.0, 1.0));
+        assert!(world.body(elevator).is_awake());
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        assert_relative_eq!(world.body(elevator).position.y, 1.0, epsilon = 1e-4);
+        assert!(world.body(rider).is_awake());
+        assert_relative_eq!(world.body(rider).position.y, 1.75, epsilon = 0.05);
+    }
+
+    #[test]
+    fn contact_events_begin_persist_and_end() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        let ground = world.create_body(BodyDef {
+            width: Vec2::new(20.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            mass: None,
+            ..Default::default()
+        });
+        let b = world.create_body(BodyDef {
+            position: Vec2::new(0.0, 0.6),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let kinds = |world: &World| -> Vec<ContactEventKind> {
+            world.contact_events().iter().map(|e| e.kind).collect()
+        };
+
+        world.step(1.0 / 60.0);
+        assert!(world.contact_events().is_empty());
+
+        let mut steps = 0;
+        while world.contact_events().is_empty() {
+            world.step(1.0 / 60.0);
+            steps += 1;
+            assert!(steps < 60, "never touched");
+        }
+        let begin = world.drain_contact_events().collect::<Vec<_>>();
+        assert_eq!(begin.len(), 1);
+        assert_eq!(begin[0].kind, ContactEventKind::Begin);
+        assert_eq!((begin[0].body1(), begin[0].body2()), (ground, b));
+        assert!(!begin[0].contacts().is_empty());
+        assert!(world.contact_events().is_empty());
+
+        world.step(1.0 / 60.0);
+        assert_eq!(kinds(&world), vec![ContactEventKind::Persist]);
+
+        // Lifted clear of the ground.
+        world.body_mut(b).position.y = 0.65;
+        world.step(1.0 / 60.0);
+        assert_eq!(kinds(&world), vec![ContactEventKind::End]);
+
+        // Destroying a body ends its contacts with the next step's events.
+        for _ in 0..30 {
+            world.step(1.0 / 60.0);
+        }
+        assert_eq!(world.arbiters.len(), 1);
+        world.destroy_body(b);
+        world.step(1.0 / 60.0);
+        assert_eq!(kinds(&world), vec![ContactEventKind::End]);
+        world.step(1.0 / 60.0);
+        assert!(world.contact_events().is_empty());
+    }
+
+    #[test]
+    fn sensors_report_enter_and_exit_without_colliding() {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        let sensor = world.create_body(BodyDef {
+            width: Vec2::new(4.0, 1.0),
+            position: Vec2::new(0.0, 0.0),
+            is_sensor: true,
+            ..Default::default()
+        });
+        let b = world.create_body(BodyDef {
+            position: Vec2::new(0.0, 2.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+
+        let mut events = Vec::new();
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+            events.extend(world.drain_sensor_events());
+        }
+
+        // Fell straight through.
+        assert!(world.arbiters.is_empty());
+        assert_relative_eq!(world.body(b).velocity.y, -10.0, epsilon = 1e-3);
+        let kinds: Vec<SensorEventKind> = events.iter().map(|e| e.kind).collect();
+        assert_eq!(kinds, vec![SensorEventKind::Enter, SensorEventKind::Exit]);
+        assert_eq!((events[0].sensor, events[0].visitor), (sensor, b));
+        assert!(world.sensor_overlaps.is_empty());
+
+        // A visitor destroyed inside the sensor exits with the next step.
+        let c = world.create_body(BodyDef {
+            position: Vec2::new(0.0, 0.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        world.step(1.0 / 60.0);
+        assert_eq!(world.sensor_events()[0].kind, SensorEventKind::Enter);
+        world.destroy_body(c);
+        world.step(1.0 / 60.0);
+        assert_eq!(
+            world.sensor_events(),
+            &[SensorEvent {
+                kind: SensorEventKind::Exit,
+                sensor,
+                visitor: c,
+                key: ArbiterKey::new(sensor, c),
+            }]
+        );
+    }
 }
@@ -1,8 +1,10 @@
 pub mod mat22;
+pub mod transform;
 pub mod utils;
 pub mod vec2;
 
 pub use mat22::Mat22;
+pub use transform::Transform;
 pub use utils::sign_nonzero;
 pub use vec2::Vec2;
 
@@ -0,0 +1,84 @@
+use core::ops::Mul;
+
+use crate::math::{Mat22, Vec2};
+
+/// Rigid transform: a rotation followed by a translation.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Transform {
+    pub position: Vec2,
+    pub rotation: Mat22,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new(Vec2::new(0.0, 0.0), 0.0)
+    }
+}
+
+impl Transform {
+    #[inline]
+    pub fn new(position: Vec2, angle: f32) -> Self {
+        Self {
+            position,
+            rotation: Mat22::from_angle(angle),
+        }
+    }
+
+    /// Local point -> world point.
+    #[inline]
+    pub fn apply(&self, v: Vec2) -> Vec2 {
+        self.position + self.rotation * v
+    }
+
+    /// World point -> local point.
+    #[inline]
+    pub fn apply_inverse(&self, v: Vec2) -> Vec2 {
+        self.rotation.transpose() * (v - self.position)
+    }
+}
+
+/// `a * b` applies `b` first, then `a`.
+impl Mul for Transform {
+    type Output = Self;
+    #[inline]
+    fn mul(self, rhs: Self) -> Self {
+        Self {
+            position: self.apply(rhs.position),
+            rotation: self.rotation * rhs.rotation,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use approx::assert_relative_eq;
+
+    #[test]
+    fn apply_rotates_then_translates() {
+        let xf = Transform::new(Vec2::new(1.0, 2.0), core::f32::consts::FRAC_PI_2);
+        let p = xf.apply(Vec2::new(1.0, 0.0));
+        assert_relative_eq!(p.x, 1.0, epsilon = 1e-5);
+        assert_relative_eq!(p.y, 3.0, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn apply_inverse_roundtrips() {
+        let xf = Transform::new(Vec2::new(-3.0, 0.5), 0.7);
+        let v = Vec2::new(2.0, -1.0);
+        let out = xf.apply_inverse(xf.apply(v));
+        assert_relative_eq!(out.x, v.x, epsilon = 1e-5);
+        assert_relative_eq!(out.y, v.y, epsilon = 1e-5);
+    }
+
+    #[test]
+    fn mul_composes_transforms() {
+        let a = Transform::new(Vec2::new(1.0, 2.0), 0.4);
+        let b = Transform::new(Vec2::new(-0.5, 3.0), -1.1);
+        let v = Vec2::new(0.3, -0.7);
+        let p = (a * b).apply(v);
+        let q = a.apply(b.apply(v));
+        assert_relative_eq!(p.x, q.x, epsilon = 1e-5);
+        assert_relative_eq!(p.y, q.y, epsilon = 1e-5);
+    }
+}
@@ -39,7 +39,7 @@ mod tests {
         let mut rng = StdRng::seed_from_u64(123);
         for _ in 0..10_000 {
             let v = random_unit(&mut rng);
-            assert!(v >= -1.0 && v <= 1.0, "random_unit out of bounds: {v}");
+            assert!((-1.0..=1.0).contains(&v), "random_unit out of bounds: {v}");
         }
     }
 
@@ -50,7 +50,7 @@ mod tests {
         let hi = 3.25;
         for _ in 0..10_000 {
             let v = random_range(&mut rng, lo, hi);
-            assert!(v >= lo && v <= hi, "random_range out of bounds: {v}");
+            assert!((lo..=hi).contains(&v), "random_range out of bounds: {v}");
         }
         // Degenerate interval should always return the endpoint.
         for _ in 0..100 {
@@ -23,6 +23,21 @@ impl Vec2 {
         (self.x * self.x + self.y * self.y).sqrt()
     }
 
+    #[inline]
+    pub fn length_squared(self) -> f32 {
+        self.x * self.x + self.y * self.y
+    }
+
+    /// Unit vector in the same direction, or zero for a (near) zero vector.
+    #[inline]
+    pub fn normalize(self) -> Self {
+        let len = self.length();
+        if len < f32::EPSILON {
+            return Self::new(0.0, 0.0);
+        }
+        (1.0 / len) * self
+    }
+
     #[inline]
     pub fn dot(self, other: Self) -> f32 {
         self.x * other.x + self.y * other.y
@@ -183,6 +198,18 @@ mod tests {
         assert_relative_eq!(b.cross(a), -1.0, epsilon = 1e-6);
     }
 
+    #[test]
+    fn normalize_unit_and_zero() {
+        let n = Vec2::new(3.0, 4.0).normalize();
+        assert_relative_eq!(n.x, 0.6, epsilon = 1e-6);
+        assert_relative_eq!(n.y, 0.8, epsilon = 1e-6);
+        assert_relative_eq!(Vec2::new(3.0, 4.0).length_squared(), 25.0, epsilon = 1e-6);
+
+        let z = Vec2::new(0.0, 0.0).normalize();
+        assert_relative_eq!(z.x, 0.0);
+        assert_relative_eq!(z.y, 0.0);
+    }
+
     #[test]
     fn cross_scalar_forms_match_box2d_lite() {
         let a = Vec2::new(2.0, 3.0);
@@ -1,8 +1,8 @@
 use approx::assert_relative_eq;
 
 use box2d_lite_rs::collision::arbiter::Contact;
-use box2d_lite_rs::collision::collide;
-use box2d_lite_rs::dynamics::{BodyDef, World};
+use box2d_lite_rs::collision::{BroadPhaseKind, Chain, Polygon, Segment, Shape, collide};
+use box2d_lite_rs::dynamics::{BodyDef, Filter, JointDef, World, WorldConfig};
 use box2d_lite_rs::math::Vec2;
 
 #[test]
@@ -55,3 +55,255 @@ fn integration_collide_overlapping_returns_contact() {
         assert!(c.separation <= 0.0);
     }
 }
+
+#[test]
+fn integration_circle_rests_on_static_box() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    world.create_body(BodyDef {
+        width: Vec2::new(20.0, 1.0),
+        position: Vec2::new(0.0, -0.5),
+        mass: None,
+        ..Default::default()
+    });
+    let ball = world.create_body(BodyDef {
+        shape: Some(Shape::Circle { radius: 0.5 }),
+        position: Vec2::new(0.0, 2.0),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+
+    for _ in 0..180 {
+        world.step(1.0 / 60.0);
+    }
+
+    let b = world.body(ball);
+    assert_relative_eq!(b.position.y, 0.5, epsilon = 0.05);
+    assert!(b.velocity.length() < 0.1);
+}
+
+#[test]
+fn integration_wedge_settles_on_ground() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    world.create_body(BodyDef {
+        width: Vec2::new(20.0, 1.0),
+        position: Vec2::new(0.0, -0.5),
+        mass: None,
+        ..Default::default()
+    });
+    let wedge = world.create_body(BodyDef {
+        shape: Some(Shape::Polygon(Polygon::new(&[
+            Vec2::new(-1.0, 0.0),
+            Vec2::new(1.0, 0.0),
+            Vec2::new(-1.0, 1.0),
+        ]))),
+        position: Vec2::new(0.0, 0.5),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+
+    for _ in 0..180 {
+        world.step(1.0 / 60.0);
+    }
+
+    // Centroid of the wedge sits a third of its height above the ground.
+    let b = world.body(wedge);
+    assert_relative_eq!(b.position.y, 1.0 / 3.0, epsilon = 0.05);
+    assert_relative_eq!(b.rotation, 0.0, epsilon = 0.05);
+    assert!(b.velocity.length() < 0.1);
+}
+
+#[test]
+fn integration_capsule_lies_down_on_ground() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    world.create_body(BodyDef {
+        width: Vec2::new(20.0, 1.0),
+        position: Vec2::new(0.0, -0.5),
+        mass: None,
+        ..Default::default()
+    });
+    let capsule = world.create_body(BodyDef {
+        shape: Some(Shape::Capsule {
+            center1: Vec2::new(-0.5, 0.0),
+            center2: Vec2::new(0.5, 0.0),
+            radius: 0.25,
+        }),
+        position: Vec2::new(0.0, 1.0),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+
+    for _ in 0..180 {
+        world.step(1.0 / 60.0);
+    }
+
+    let b = world.body(capsule);
+    assert_relative_eq!(b.position.y, 0.25, epsilon = 0.05);
+    assert_relative_eq!(b.rotation, 0.0, epsilon = 0.05);
+    assert!(b.velocity.length() < 0.1);
+}
+
+#[test]
+fn integration_box_slides_over_chain_without_snagging() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    // Flat terrain of unit edges, listed right to left so the solid side is up.
+    let vertices: Vec<Vec2> = (0..=20)
+        .rev()
+        .map(|k| Vec2::new(k as f32 - 10.0, 0.0))
+        .collect();
+    let ghost_prev = Vec2::new(11.0, 0.0);
+    let ghost_next = Vec2::new(-11.0, 0.0);
+    world.create_body(BodyDef {
+        shape: Some(Shape::Chain(Chain::new_open(
+            vertices, ghost_prev, ghost_next,
+        ))),
+        mass: None,
+        friction: 0.0,
+        ..Default::default()
+    });
+    let block = world.create_body(BodyDef {
+        width: Vec2::new(0.5, 0.5),
+        position: Vec2::new(-8.0, 0.25),
+        friction: 0.0,
+        mass: Some(1.0),
+        ..Default::default()
+    });
+    world.body_mut(block).velocity = Vec2::new(5.0, 0.0);
+
+    for _ in 0..120 {
+        world.step(1.0 / 60.0);
+    }
+
+    // Ten internal vertices later, the block is still sliding at full speed.
+    let b = world.body(block);
+    assert!(b.position.x > 1.0);
+    assert_relative_eq!(b.velocity.x, 5.0, epsilon = 0.05);
+    assert_relative_eq!(b.position.y, 0.25, epsilon = 0.02);
+    assert_relative_eq!(b.rotation, 0.0, epsilon = 0.01);
+}
+
+#[test]
+fn integration_ball_rests_on_segment() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    world.create_body(BodyDef {
+        shape: Some(Shape::Segment(Segment::two_sided(
+            Vec2::new(-5.0, 0.0),
+            Vec2::new(5.0, 0.0),
+        ))),
+        mass: None,
+        ..Default::default()
+    });
+    let ball = world.create_body(BodyDef {
+        shape: Some(Shape::Circle { radius: 0.5 }),
+        position: Vec2::new(0.0, 2.0),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+
+    for _ in 0..180 {
+        world.step(1.0 / 60.0);
+    }
+
+    let b = world.body(ball);
+    assert_relative_eq!(b.position.y, 0.5, epsilon = 0.05);
+    assert!(b.velocity.length() < 0.1);
+}
+
+#[test]
+fn integration_bullets_do_not_tunnel_through_thin_walls() {
+    let fire = |bullet: bool, broad_phase: BroadPhaseKind| {
+        let config = WorldConfig {
+            broad_phase,
+            ..Default::default()
+        };
+        let mut world = World::with_config(Vec2::new(0.0, 0.0), 10, config);
+        world.create_body(BodyDef {
+            width: Vec2::new(0.1, 4.0),
+            mass: None,
+            ..Default::default()
+        });
+        let h = world.create_body(BodyDef {
+            width: Vec2::new(0.5, 0.5),
+            position: Vec2::new(-3.0, 0.0),
+            mass: Some(1.0),
+            bullet,
+            ..Default::default()
+        });
+        // 5 m per step, far more than the wall and bullet are thick.
+        world.body_mut(h).velocity = Vec2::new(300.0, 0.0);
+
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        world.body(h).position.x
+    };
+
+    assert!(fire(false, BroadPhaseKind::default()) > 1.0);
+
+    // The wall is found through whichever broad-phase the world uses.
+    for kind in [
+        BroadPhaseKind::DynamicTree,
+        BroadPhaseKind::SweepAndPrune,
+        BroadPhaseKind::BruteForce,
+    ] {
+        let x = fire(true, kind);
+        // Resting against the wall: its face at -0.05, the bullet's half width 0.25.
+        assert!(x < -0.28, "bullet passed the wall with {kind:?}: x = {x}");
+        assert!(x > -0.32);
+    }
+}
+
+#[test]
+fn integration_bullets_pass_what_they_do_not_collide_with() {
+    let fire = |bullet: bool, filtered: bool, jointed: bool| {
+        let mut world = World::new(Vec2::new(0.0, 0.0), 10);
+        let wall = world.create_body(BodyDef {
+            width: Vec2::new(0.1, 4.0),
+            mass: None,
+            filter: Filter {
+                category_bits: 0x0002,
+                mask_bits: if filtered { !0x0004 } else { 0xFFFF },
+                group_index: 0,
+            },
+            ..Default::default()
+        });
+        let h = world.create_body(BodyDef {
+            width: Vec2::new(0.5, 0.5),
+            position: Vec2::new(-3.0, 0.0),
+            mass: Some(1.0),
+            bullet,
+            filter: Filter {
+                category_bits: 0x0004,
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        if jointed {
+            // Soft enough not to hold the bullet back.
+            world.create_joint(JointDef {
+                softness: 1.0e6,
+                bias_factor: 0.0,
+                ..JointDef::new(wall, h, Vec2::new(0.0, 0.0))
+            });
+        }
+        world.body_mut(h).velocity = Vec2::new(300.0, 0.0);
+
+        for _ in 0..10 {
+            world.step(1.0 / 60.0);
+        }
+        world.body(h).position.x
+    };
+
+    assert!(fire(true, false, false) < -0.28);
+
+    // Not even held up for a step: it flies as if it were no bullet.
+    for (filtered, jointed) in [(true, false), (false, true)] {
+        let x = fire(true, filtered, jointed);
+        assert!(x > 1.0, "wall stopped the bullet at x = {x}");
+        assert_relative_eq!(x, fire(false, filtered, jointed), epsilon = 1e-3);
+    }
+}
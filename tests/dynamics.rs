@@ -1,6 +1,13 @@
 use approx::assert_relative_eq;
 
-use box2d_lite_rs::dynamics::{BodyDef, World};
+use std::cell::Cell;
+use std::rc::Rc;
+
+use box2d_lite_rs::collision::{Arbiter, ArbiterKey, Shape};
+use box2d_lite_rs::dynamics::{
+    Body, BodyDef, BodyHandle, BodyType, ContactListener, Filter, FixtureDef, Material, SlotMap,
+    World, WorldConfig,
+};
 use box2d_lite_rs::math::Vec2;
 
 #[test]
@@ -20,3 +27,432 @@ fn integration_smoke_world_step() {
     assert!(b.position.y < 1.0);
     assert_relative_eq!(b.velocity.x, 0.0, epsilon = 1e-6);
 }
+
+#[test]
+fn integration_table_stands_on_its_legs() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    let ground = world.create_body(BodyDef {
+        width: Vec2::new(20.0, 1.0),
+        position: Vec2::new(0.0, -0.5),
+        mass: None,
+        ..Default::default()
+    });
+
+    // Top 3 x 0.2 resting on two 0.2 x 1 legs, all one rigid body.
+    let leg = |x: f32| FixtureDef {
+        shape: Shape::Box {
+            width: Vec2::new(0.2, 1.0),
+        },
+        offset: Vec2::new(x, 0.5),
+        ..Default::default()
+    };
+    let table = world.create_body(BodyDef {
+        fixtures: vec![
+            FixtureDef {
+                shape: Shape::Box {
+                    width: Vec2::new(3.0, 0.2),
+                },
+                offset: Vec2::new(0.0, 1.1),
+                ..Default::default()
+            },
+            leg(-1.2),
+            leg(1.2),
+        ],
+        position: Vec2::new(0.0, 0.5),
+        mass: Some(5.0),
+        ..Default::default()
+    });
+
+    for _ in 0..180 {
+        world.step(1.0 / 60.0);
+    }
+
+    let b = world.body(table);
+    assert_relative_eq!(b.rotation, 0.0, epsilon = 0.01);
+    assert!(b.velocity.length() < 0.1);
+
+    // The top sits on the legs, clear of the ground.
+    let top = b.fixture_transform(0).position;
+    assert_relative_eq!(top.y, 1.1, epsilon = 0.05);
+
+    // One arbiter per touching leg.
+    let legs: Vec<_> = world
+        .arbiters
+        .keys()
+        .filter(|k| k.body1 == ground)
+        .collect();
+    assert_eq!(legs.len(), 2);
+    assert!(legs.iter().all(|k| k.fixture2 != 0));
+}
+
+#[test]
+fn integration_kinematic_platform_carries_a_box() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    let platform = world.create_body(BodyDef {
+        width: Vec2::new(4.0, 0.5),
+        position: Vec2::new(0.0, 0.0),
+        friction: 0.8,
+        body_type: Some(BodyType::Kinematic),
+        ..Default::default()
+    });
+    world.body_mut(platform).velocity = Vec2::new(1.0, 0.5);
+
+    let crate_ = world.create_body(BodyDef {
+        position: Vec2::new(0.0, 0.75),
+        friction: 0.8,
+        mass: Some(10.0),
+        ..Default::default()
+    });
+
+    for _ in 0..120 {
+        world.step(1.0 / 60.0);
+    }
+
+    // The platform follows its velocity exactly, ignoring gravity and the box.
+    let p = world.body(platform);
+    assert_relative_eq!(p.position.x, 2.0, epsilon = 1e-4);
+    assert_relative_eq!(p.position.y, 1.0, epsilon = 1e-4);
+    assert_relative_eq!(p.velocity.x, 1.0);
+    assert_relative_eq!(p.velocity.y, 0.5);
+
+    // Friction drags the box along, still resting on top.
+    let b = world.body(crate_);
+    assert_relative_eq!(b.velocity.x, 1.0, epsilon = 0.05);
+    assert_relative_eq!(b.position.x, p.position.x, epsilon = 0.1);
+    assert_relative_eq!(b.position.y - p.position.y, 0.75, epsilon = 0.05);
+}
+
+fn pyramid(config: WorldConfig) -> World {
+    let mut world = World::with_config(Vec2::new(0.0, -10.0), 10, config);
+    world.create_body(BodyDef {
+        width: Vec2::new(40.0, 1.0),
+        position: Vec2::new(0.0, -0.5),
+        mass: None,
+        ..Default::default()
+    });
+    for row in 0..5 {
+        for k in 0..(5 - row) {
+            let x = -2.0 + 1.05 * k as f32 + 0.525 * row as f32;
+            world.create_body(BodyDef {
+                position: Vec2::new(x, 0.5 + 1.01 * row as f32),
+                mass: Some(1.0),
+                ..Default::default()
+            });
+        }
+    }
+    world
+}
+
+#[test]
+fn integration_settled_pyramid_falls_asleep_and_wakes_on_impact() {
+    let mut world = pyramid(WorldConfig::default());
+    for _ in 0..300 {
+        world.step(1.0 / 60.0);
+    }
+    assert!(world.bodies.values().all(|b| !b.is_awake()));
+    let resting: Vec<Vec2> = world.bodies.values().map(|b| b.position).collect();
+
+    // Sleeping bodies stay exactly where they are.
+    for _ in 0..60 {
+        world.step(1.0 / 60.0);
+    }
+    let after: Vec<Vec2> = world.bodies.values().map(|b| b.position).collect();
+    assert_eq!(resting, after);
+
+    // A box dropped on top wakes the pyramid through the new contact.
+    let dropped = world.create_body(BodyDef {
+        position: Vec2::new(0.1, 7.0),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+    for _ in 0..60 {
+        world.step(1.0 / 60.0);
+        if world.arbiters.keys().any(|k| k.body2 == dropped) {
+            break;
+        }
+    }
+    world.step(1.0 / 60.0);
+    assert!(world.bodies.values().filter(|b| b.is_awake()).count() > 2);
+}
+
+#[test]
+fn integration_sleeping_can_be_disabled() {
+    let mut world = pyramid(WorldConfig {
+        allow_sleeping: false,
+        ..WorldConfig::default()
+    });
+    for _ in 0..300 {
+        world.step(1.0 / 60.0);
+    }
+    assert!(
+        world
+            .bodies
+            .values()
+            .all(|b| b.is_awake() || b.body_type == BodyType::Static)
+    );
+}
+
+/// Lets bodies pass up through `platform` and records the largest normal
+/// impulse it takes.
+struct OneWayPlatform {
+    platform: BodyHandle,
+    top: f32,
+    max_impulse: Rc<Cell<f32>>,
+}
+
+impl ContactListener for OneWayPlatform {
+    fn pre_solve(
+        &mut self,
+        key: &ArbiterKey,
+        _arb: &mut Arbiter,
+        bodies: &SlotMap<BodyHandle, Body>,
+    ) -> bool {
+        let other = match (key.body1 == self.platform, key.body2 == self.platform) {
+            (true, _) => key.body2,
+            (_, true) => key.body1,
+            _ => return true,
+        };
+        // Only solid once the body's underside is above the platform.
+        bodies[other].position.y - 0.5 > self.top - 0.05
+    }
+
+    fn post_solve(&mut self, key: &ArbiterKey, arb: &Arbiter, _: &SlotMap<BodyHandle, Body>) {
+        if key.body1 == self.platform || key.body2 == self.platform {
+            for c in &arb.contacts[..arb.num_contacts] {
+                self.max_impulse.set(self.max_impulse.get().max(c.pn));
+            }
+        }
+    }
+}
+
+#[test]
+fn integration_one_way_platform_via_contact_listener() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+    let platform = world.create_body(BodyDef {
+        width: Vec2::new(4.0, 0.2),
+        position: Vec2::new(0.0, 2.0),
+        mass: None,
+        ..Default::default()
+    });
+    let h = world.create_body(BodyDef {
+        position: Vec2::new(0.0, 0.5),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+    world.body_mut(h).velocity = Vec2::new(0.0, 8.0);
+
+    let max_impulse = Rc::new(Cell::new(0.0));
+    world.set_contact_listener(Some(Box::new(OneWayPlatform {
+        platform,
+        top: 2.1,
+        max_impulse: max_impulse.clone(),
+    })));
+
+    for _ in 0..180 {
+        world.step(1.0 / 60.0);
+    }
+
+    // Jumped through from below and landed on top.
+    assert_relative_eq!(world.body(h).position.y, 2.6, epsilon = 0.02);
+    assert!(max_impulse.get() > 0.0);
+}
+
+/// Keeps each bullet from hitting the body that fired it.
+struct IgnoreShooter {
+    shooter: BodyHandle,
+    bullet: BodyHandle,
+}
+
+impl ContactListener for IgnoreShooter {
+    fn should_collide(
+        &mut self,
+        a: BodyHandle,
+        b: BodyHandle,
+        _: &SlotMap<BodyHandle, Body>,
+    ) -> bool {
+        (a, b) != (self.shooter, self.bullet) && (b, a) != (self.shooter, self.bullet)
+    }
+}
+
+#[test]
+fn integration_filters_and_should_collide_skip_pairs() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+    let ground = world.create_body(BodyDef {
+        width: Vec2::new(20.0, 1.0),
+        position: Vec2::new(0.0, -0.5),
+        mass: None,
+        ..Default::default()
+    });
+
+    // Two limbs of one ragdoll overlap without pushing apart, but both
+    // still land on the ground.
+    let limb = Filter {
+        group_index: -1,
+        ..Filter::default()
+    };
+    let limbs: Vec<BodyHandle> = [0.0, 0.4]
+        .iter()
+        .map(|&x| {
+            world.create_body(BodyDef {
+                position: Vec2::new(x, 0.5),
+                mass: Some(1.0),
+                filter: limb,
+                ..Default::default()
+            })
+        })
+        .collect();
+
+    // A box whose mask leaves out the ground's category falls through it.
+    let ghost = world.create_body(BodyDef {
+        position: Vec2::new(5.0, 0.5),
+        mass: Some(1.0),
+        filter: Filter {
+            mask_bits: !0x0001,
+            category_bits: 0x0002,
+            group_index: 0,
+        },
+        ..Default::default()
+    });
+
+    // A shot starting inside its shooter.
+    let shooter = world.create_body(BodyDef {
+        position: Vec2::new(-5.0, 0.5),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+    let bullet = world.create_body(BodyDef {
+        width: Vec2::new(0.2, 0.2),
+        position: Vec2::new(-5.0, 0.5),
+        mass: Some(0.1),
+        ..Default::default()
+    });
+    world.set_contact_listener(Some(Box::new(IgnoreShooter { shooter, bullet })));
+
+    for _ in 0..60 {
+        world.step(1.0 / 60.0);
+    }
+
+    for &h in &limbs {
+        assert_relative_eq!(world.body(h).position.y, 0.5, epsilon = 0.02);
+    }
+    assert_relative_eq!(world.body(limbs[0]).position.x, 0.0, epsilon = 0.01);
+    assert_relative_eq!(world.body(limbs[1]).position.x, 0.4, epsilon = 0.01);
+    assert!(world.body(ghost).position.y < -2.0);
+    assert_relative_eq!(world.body(shooter).position.x, -5.0, epsilon = 0.01);
+    // The bullet fell through its shooter onto the ground.
+    assert_relative_eq!(world.body(bullet).position.y, 0.1, epsilon = 0.02);
+    let skipped = [
+        ArbiterKey::new(limbs[0], limbs[1]),
+        ArbiterKey::new(shooter, bullet),
+        ArbiterKey::new(ground, ghost),
+    ];
+    assert!(world.arbiters.keys().all(|k| !skipped.contains(k)));
+}
+
+#[test]
+fn integration_restitution_bounces_until_below_threshold() {
+    let drop = |restitution: f32| {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        world.create_body(BodyDef {
+            width: Vec2::new(20.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            mass: None,
+            ..Default::default()
+        });
+        let ball = world.create_body(BodyDef {
+            shape: Some(Shape::Circle { radius: 0.5 }),
+            position: Vec2::new(0.0, 3.0),
+            restitution,
+            mass: Some(1.0),
+            ..Default::default()
+        });
+
+        // Highest point after the first contact.
+        let mut touched = false;
+        let mut rebound = f32::MIN;
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+            touched |= !world.arbiters.is_empty();
+            if touched {
+                rebound = rebound.max(world.body(ball).position.y);
+            }
+        }
+        assert!(touched);
+        for _ in 0..600 {
+            world.step(1.0 / 60.0);
+        }
+        (rebound, world.body(ball).velocity.y)
+    };
+
+    // Dropped 2.5 m, so an elastic bounce would come back up to 3.
+    let (rebound, _) = drop(0.0);
+    assert!(rebound < 0.6, "{rebound}");
+    let (rebound, settled) = drop(0.8);
+    assert!(rebound > 2.0 && rebound < 3.0, "{rebound}");
+    // Bounces slower than the threshold are absorbed.
+    assert!(settled.abs() < 0.1, "{settled}");
+}
+
+#[test]
+fn integration_conveyor_belt_and_rolling_resistance() {
+    let ground = |world: &mut World, material: Material| {
+        world.create_body(BodyDef {
+            width: Vec2::new(40.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            material: Some(material),
+            mass: None,
+            ..Default::default()
+        });
+    };
+
+    // The belt's top moves clockwise, to the right, and carries the box.
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+    ground(
+        &mut world,
+        Material {
+            friction: 0.8,
+            tangent_speed: 2.0,
+            ..Default::default()
+        },
+    );
+    let crate_ = world.create_body(BodyDef {
+        position: Vec2::new(0.0, 0.5),
+        friction: 0.8,
+        mass: Some(1.0),
+        ..Default::default()
+    });
+    for _ in 0..120 {
+        world.step(1.0 / 60.0);
+    }
+    assert_relative_eq!(world.body(crate_).velocity.x, 2.0, epsilon = 0.05);
+
+    // A rolling ball slows down on a resistant surface only.
+    let roll = |rolling_resistance: f32| {
+        let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+        ground(
+            &mut world,
+            Material {
+                rolling_resistance,
+                ..Default::default()
+            },
+        );
+        let ball = world.create_body(BodyDef {
+            shape: Some(Shape::Circle { radius: 0.5 }),
+            position: Vec2::new(0.0, 0.5),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        world.body_mut(ball).velocity.x = 3.0;
+        world.body_mut(ball).angular_velocity = -6.0;
+        for _ in 0..60 {
+            world.step(1.0 / 60.0);
+        }
+        world.body(ball).velocity.x
+    };
+    let free = roll(0.0);
+    let resisted = roll(0.2);
+    assert_relative_eq!(free, 3.0, epsilon = 0.05);
+    assert!(resisted < 2.5, "{resisted}");
+}
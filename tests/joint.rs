@@ -1,9 +1,9 @@
 use approx::assert_relative_eq;
 
-use box2d_lite_rs::dynamics::{BodyDef, JointDef, World, WorldConfig};
+use box2d_lite_rs::dynamics::{BodyDef, BodyHandle, JointDef, World, WorldConfig};
 use box2d_lite_rs::math::Vec2;
 
-fn make_two_bodies_with_joint(config: WorldConfig) -> (World, usize, usize) {
+fn make_two_bodies_with_joint(config: WorldConfig) -> (World, BodyHandle, BodyHandle) {
     let mut world = World::with_config(Vec2::new(0.0, 0.0), 20, config);
 
     let b1 = world.create_body(BodyDef {
@@ -22,7 +22,7 @@ fn make_two_bodies_with_joint(config: WorldConfig) -> (World, usize, usize) {
     // Joint anchor at origin.
     world.create_joint(JointDef::new(b1, b2, Vec2::new(0.0, 0.0)));
 
-    (world, b1.0, b2.0)
+    (world, b1, b2)
 }
 
 #[test]
@@ -106,3 +106,46 @@ fn integration_joint_warm_starting_changes_first_step_response() {
         epsilon = 1e-2
     );
 }
+
+#[test]
+fn integration_breaking_a_chain_releases_the_lower_links() {
+    let mut world = World::new(Vec2::new(0.0, -10.0), 10);
+
+    let anchor = world.create_body(BodyDef {
+        position: Vec2::new(0.0, 10.0),
+        mass: None,
+        ..Default::default()
+    });
+    let mut links = Vec::new();
+    let mut joints = Vec::new();
+    let mut prev = anchor;
+    for k in 1..=3 {
+        let link = world.create_body(BodyDef {
+            width: Vec2::new(0.5, 0.5),
+            position: Vec2::new(0.0, 10.0 - k as f32),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let at = Vec2::new(0.0, 10.5 - k as f32);
+        joints.push(world.create_joint(JointDef::new(prev, link, at)));
+        links.push(link);
+        prev = link;
+    }
+
+    for _ in 0..60 {
+        world.step(1.0 / 60.0);
+    }
+    let hanging = world.body(links[2]).position.y;
+    assert!(hanging > 6.0);
+
+    // Cut the middle joint: the top link stays, the bottom one falls.
+    let cut = world.destroy_joint(joints[1]).expect("joint is alive");
+    assert_eq!(cut.bodies(), (links[0], links[1]));
+    for _ in 0..60 {
+        world.step(1.0 / 60.0);
+    }
+
+    assert_relative_eq!(world.body(links[0]).position.y, 9.0, epsilon = 0.1);
+    assert!(world.body(links[2]).position.y < hanging - 3.0);
+    assert_eq!(world.joints.len(), 2);
+}
@@ -1,5 +1,11 @@
 use approx::assert_relative_eq;
 
+use std::cell::Cell;
+use std::rc::Rc;
+
+use box2d_lite_rs::collision::{
+    Aabb, BroadPhase, BroadPhaseKind, BruteForceBroadPhase, FaceTolerance, FixtureProxy, ProxyId,
+};
 use box2d_lite_rs::dynamics::{BodyDef, World, WorldConfig};
 use box2d_lite_rs::math::Vec2;
 
@@ -71,8 +77,316 @@ fn integration_config_position_correction_disables_bias() {
 
     // Compare absolute x-velocity magnitude after a step;
     // position correction typically generates larger separating velocities.
-    let v_on = w_on.bodies[0].velocity.x.abs() + w_on.bodies[1].velocity.x.abs();
-    let v_off = w_off.bodies[0].velocity.x.abs() + w_off.bodies[1].velocity.x.abs();
+    let v_on: f32 = w_on.bodies.values().map(|b| b.velocity.x.abs()).sum();
+    let v_off: f32 = w_off.bodies.values().map(|b| b.velocity.x.abs()).sum();
 
     assert!(v_on >= v_off);
 }
+
+#[test]
+fn integration_broad_phases_give_identical_simulations() {
+    let run = |broad_phase: BroadPhaseKind| {
+        let mut world = World::with_config(
+            Vec2::new(0.0, -10.0),
+            10,
+            WorldConfig {
+                broad_phase,
+                ..Default::default()
+            },
+        );
+        world.create_body(BodyDef {
+            width: Vec2::new(40.0, 1.0),
+            position: Vec2::new(0.0, -0.5),
+            mass: None,
+            ..Default::default()
+        });
+        // A row of small pyramids along x.
+        for base in 0..4 {
+            for row in 0..3 {
+                for k in 0..(3 - row) {
+                    let x = 8.0 * base as f32 - 12.0 + 1.05 * k as f32 + 0.525 * row as f32;
+                    world.create_body(BodyDef {
+                        position: Vec2::new(x, 0.5 + 1.01 * row as f32),
+                        mass: Some(1.0),
+                        ..Default::default()
+                    });
+                }
+            }
+        }
+
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let poses: Vec<(Vec2, f32)> = world
+            .bodies
+            .values()
+            .map(|b| (b.position, b.rotation))
+            .collect();
+        (poses, world.arbiters.len())
+    };
+
+    let (reference, reference_arbiters) = run(BroadPhaseKind::BruteForce);
+    for kind in [BroadPhaseKind::DynamicTree, BroadPhaseKind::SweepAndPrune] {
+        let (poses, arbiters) = run(kind);
+        assert_eq!(arbiters, reference_arbiters);
+        for ((p1, r1), (p2, r2)) in poses.iter().zip(&reference) {
+            assert_relative_eq!(p1.x, p2.x, epsilon = 1e-6);
+            assert_relative_eq!(p1.y, p2.y, epsilon = 1e-6);
+            assert_relative_eq!(*r1, *r2, epsilon = 1e-6);
+        }
+    }
+}
+
+/// Plug-in broad-phase that counts how often the world asks for pairs.
+struct CountingBroadPhase {
+    inner: BruteForceBroadPhase,
+    updates: Rc<Cell<usize>>,
+}
+
+impl BroadPhase for CountingBroadPhase {
+    fn create_proxy(&mut self, aabb: Aabb, proxy: FixtureProxy) -> ProxyId {
+        self.inner.create_proxy(aabb, proxy)
+    }
+
+    fn destroy_proxy(&mut self, id: ProxyId) {
+        self.inner.destroy_proxy(id);
+    }
+
+    fn move_proxy(&mut self, id: ProxyId, aabb: Aabb) {
+        self.inner.move_proxy(id, aabb);
+    }
+
+    fn fat_aabb(&self, id: ProxyId) -> Aabb {
+        self.inner.fat_aabb(id)
+    }
+
+    fn proxy_count(&self) -> usize {
+        self.inner.proxy_count()
+    }
+
+    fn update_pairs(&mut self, callback: &mut dyn FnMut(FixtureProxy, FixtureProxy)) {
+        self.updates.set(self.updates.get() + 1);
+        self.inner.update_pairs(callback);
+    }
+
+    fn clear(&mut self) {
+        self.inner.clear();
+    }
+
+    fn query(&self, aabb: &Aabb, callback: &mut dyn FnMut(FixtureProxy) -> bool) {
+        self.inner.query(aabb, callback);
+    }
+}
+
+#[test]
+fn integration_world_runs_on_a_custom_broad_phase() {
+    let updates = Rc::new(Cell::new(0));
+    let mut world = World::with_broad_phase(
+        Vec2::new(0.0, -10.0),
+        10,
+        WorldConfig::default(),
+        Box::new(CountingBroadPhase {
+            inner: BruteForceBroadPhase::new(0.1),
+            updates: updates.clone(),
+        }),
+    );
+
+    world.create_body(BodyDef {
+        width: Vec2::new(20.0, 1.0),
+        position: Vec2::new(0.0, -0.5),
+        mass: None,
+        ..Default::default()
+    });
+    let h = world.create_body(BodyDef {
+        position: Vec2::new(0.0, 1.0),
+        mass: Some(1.0),
+        ..Default::default()
+    });
+    assert_eq!(world.proxies.proxy_count(), 2);
+
+    for _ in 0..120 {
+        world.step(1.0 / 60.0);
+    }
+
+    assert_eq!(updates.get(), 120);
+    assert_relative_eq!(world.body(h).position.y, 0.5, epsilon = 0.05);
+}
+
+#[test]
+fn integration_config_validate_rejects_out_of_range_values() {
+    assert_eq!(WorldConfig::default().validate(), Ok(()));
+
+    let bad = [
+        WorldConfig {
+            allowed_penetration: -0.01,
+            ..Default::default()
+        },
+        WorldConfig {
+            bias_factor: 1.5,
+            ..Default::default()
+        },
+        WorldConfig {
+            max_linear_correction: 0.0,
+            ..Default::default()
+        },
+        WorldConfig {
+            face_tolerance: FaceTolerance {
+                relative_tol: f32::NAN,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        WorldConfig {
+            aabb_margin: -0.1,
+            ..Default::default()
+        },
+        WorldConfig {
+            toi_target: 0.0,
+            ..Default::default()
+        },
+    ];
+    let fields: Vec<_> = bad
+        .iter()
+        .map(|c| c.validate().unwrap_err().field)
+        .collect();
+    assert_eq!(
+        fields,
+        [
+            "allowed_penetration",
+            "bias_factor",
+            "max_linear_correction",
+            "face_tolerance.relative_tol",
+            "aabb_margin",
+            "toi_target",
+        ]
+    );
+}
+
+#[test]
+#[should_panic(expected = "bias_factor")]
+fn integration_world_rejects_invalid_config() {
+    World::with_config(
+        Vec2::new(0.0, -10.0),
+        10,
+        WorldConfig {
+            bias_factor: -0.2,
+            ..Default::default()
+        },
+    );
+}
+
+#[test]
+fn integration_try_with_config_returns_the_error() {
+    let err = World::try_with_config(
+        Vec2::new(0.0, -10.0),
+        10,
+        WorldConfig {
+            toi_target: f32::NAN,
+            ..Default::default()
+        },
+    )
+    .err()
+    .unwrap();
+    assert_eq!(err.field, "toi_target");
+
+    assert!(World::try_with_config(Vec2::new(0.0, -10.0), 10, WorldConfig::default()).is_ok());
+}
+
+#[test]
+fn integration_config_aabb_margin_fattens_proxies() {
+    for broad_phase in [
+        BroadPhaseKind::DynamicTree,
+        BroadPhaseKind::SweepAndPrune,
+        BroadPhaseKind::BruteForce,
+    ] {
+        let mut world = World::with_config(
+            Vec2::new(0.0, -1000.0),
+            10,
+            WorldConfig {
+                broad_phase,
+                aabb_margin: 10.0,
+                ..Default::default()
+            },
+        );
+        let h = world.create_body(BodyDef {
+            width: Vec2::new(100.0, 100.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+
+        let fat = world.proxies.fat_aabb(world.body(h).fixtures[0].proxies[0]);
+        assert_relative_eq!(fat.upper.x, 60.0, epsilon = 1e-4);
+        assert_relative_eq!(fat.lower.y, -60.0, epsilon = 1e-4);
+    }
+}
+
+#[test]
+fn integration_config_max_linear_correction_limits_push_out() {
+    let separation_speed = |max_linear_correction: f32| {
+        let mut world = World::with_config(
+            Vec2::new(0.0, 0.0),
+            10,
+            WorldConfig {
+                max_linear_correction,
+                ..Default::default()
+            },
+        );
+        // Overlapping by half their width.
+        let a = world.create_body(BodyDef {
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        let b = world.create_body(BodyDef {
+            position: Vec2::new(0.5, 0.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        world.step(0.1);
+        world.body(b).velocity.x - world.body(a).velocity.x
+    };
+
+    // Unclamped, a fifth of the overlap goes in one step.
+    assert_relative_eq!(
+        separation_speed(f32::INFINITY),
+        0.2 * 0.49 / 0.1,
+        epsilon = 1e-3
+    );
+    assert_relative_eq!(separation_speed(0.05), 0.05 / 0.1, epsilon = 1e-3);
+}
+
+#[test]
+fn integration_config_tolerances_scale_to_centimetres() {
+    // A 1 m box dropped from 1 m, in centimetres.
+    let resting_penetration = |config: WorldConfig| {
+        let mut world = World::with_config(Vec2::new(0.0, -1000.0), 10, config);
+        world.create_body(BodyDef {
+            width: Vec2::new(2000.0, 100.0),
+            position: Vec2::new(0.0, -50.0),
+            mass: None,
+            ..Default::default()
+        });
+        world.create_body(BodyDef {
+            width: Vec2::new(100.0, 100.0),
+            position: Vec2::new(0.0, 150.0),
+            mass: Some(1.0),
+            ..Default::default()
+        });
+        for _ in 0..120 {
+            world.step(1.0 / 60.0);
+        }
+        let arb = world.arbiters.values().next().unwrap();
+        -arb.contacts[..arb.num_contacts]
+            .iter()
+            .map(|c| c.separation)
+            .fold(f32::MAX, f32::min)
+    };
+
+    let metres = resting_penetration(WorldConfig::default());
+    let centimetres = resting_penetration(WorldConfig {
+        allowed_penetration: 1.0,
+        ..Default::default()
+    });
+    // Contacts come to rest at the allowed penetration.
+    assert_relative_eq!(metres, 0.01, epsilon = 1e-3);
+    assert_relative_eq!(centimetres, 1.0, epsilon = 1e-2);
+}